@@ -0,0 +1,17 @@
+//! Generate the C header from ``src/lib.rs`` into ``OUT_DIR``, where ``test_header`` compares it with ``include/access_json.h``.
+//!
+//! Set ``ACCESS_JSON_WRITE_HEADER=1`` to update ``include/access_json.h`` too; we never write to the source tree otherwise.
+fn main() {
+    let dir = std::env::var("CARGO_MANIFEST_DIR").unwrap();
+    let out = std::env::var("OUT_DIR").unwrap();
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+    println!("cargo:rerun-if-env-changed=ACCESS_JSON_WRITE_HEADER");
+    let config = cbindgen::Config::from_file(format!("{}/cbindgen.toml", dir)).unwrap();
+    let header =
+        cbindgen::generate_with_config(&dir, config).expect("could not generate the C header");
+    header.write_to_file(format!("{}/access_json.h", out));
+    if std::env::var_os("ACCESS_JSON_WRITE_HEADER").is_some() {
+        header.write_to_file(format!("{}/include/access_json.h", dir));
+    }
+}
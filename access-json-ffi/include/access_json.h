@@ -0,0 +1,94 @@
+#ifndef ACCESS_JSON_H
+#define ACCESS_JSON_H
+
+/* Generated by build.rs from access-json-ffi/src/lib.rs; do not edit. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// What ``access_json_query`` did; anything but ``Ok`` and ``NoMatch`` leaves a message in the output buffer.
+//
+// Codes from 100 to 199 are ``QueryParseErr`` variants, and codes from 200 to 299 are ``QueryExecErr`` variants.
+typedef enum AccessJsonStatus {
+  // The buffer holds the result as JSON.
+  ACCESS_JSON_STATUS_OK = 0,
+  // The query found nothing; the buffer is empty.
+  ACCESS_JSON_STATUS_NO_MATCH = 1,
+  // A pointer was null, or a string was not UTF-8.
+  ACCESS_JSON_STATUS_BAD_ARGUMENT = 2,
+  // Something went wrong inside access-json; please report it!
+  ACCESS_JSON_STATUS_PANIC = 3,
+  ACCESS_JSON_STATUS_PARSE_BAD_CHARACTER = 100,
+  ACCESS_JSON_STATUS_PARSE_MISSING_FIELD = 101,
+  ACCESS_JSON_STATUS_PARSE_MISSING_NUMBER = 102,
+  ACCESS_JSON_STATUS_PARSE_BAD_ARRAY = 103,
+  ACCESS_JSON_STATUS_PARSE_BAD_FIELD = 104,
+  ACCESS_JSON_STATUS_PARSE_UNEXPECTED_EOF = 105,
+  ACCESS_JSON_STATUS_PARSE_UNEXPECTED = 106,
+  ACCESS_JSON_STATUS_PARSE_BAD_INDEX = 107,
+  ACCESS_JSON_STATUS_PARSE_BAD_SLICE = 108,
+  ACCESS_JSON_STATUS_PARSE_BAD_FILTER = 109,
+  ACCESS_JSON_STATUS_PARSE_BAD_STRING = 110,
+  ACCESS_JSON_STATUS_PARSE_UNSUPPORTED = 111,
+  ACCESS_JSON_STATUS_PARSE_BAD_ESCAPE = 112,
+  ACCESS_JSON_STATUS_EXEC_EMPTY_QUERY = 200,
+  ACCESS_JSON_STATUS_EXEC_INTERNAL_ERROR = 201,
+  ACCESS_JSON_STATUS_EXEC_SERIALIZATION = 202,
+  ACCESS_JSON_STATUS_EXEC_DESERIALIZATION = 203,
+  ACCESS_JSON_STATUS_EXEC_WRONG_LENGTH = 204,
+  ACCESS_JSON_STATUS_EXEC_BAD_MAP_KEY = 205,
+  ACCESS_JSON_STATUS_EXEC_FORBIDDEN = 206,
+  ACCESS_JSON_STATUS_EXEC_UNSUPPORTED = 207,
+} AccessJsonStatus;
+
+// An opaque, read-only root to run queries against.
+typedef struct AccessJsonHandle AccessJsonHandle;
+
+// UTF-8 text owned by access-json; free it with ``access_json_buffer_free``.
+//
+// ``data`` is followed by a NUL byte that ``len`` does not count, so it can be used as a C string too.
+typedef struct AccessJsonBuffer {
+  char *data;
+  uintptr_t len;
+} AccessJsonBuffer;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Make a handle for a JSON document, e.g., from C code that has no Rust root of its own.
+//
+// Returns null if ``json`` is null, not UTF-8, or not JSON.
+//
+// # Safety
+// ``json`` must be null or point to ``len`` readable bytes.
+struct AccessJsonHandle *access_json_handle_from_json(const char *json, uintptr_t len);
+
+// Free a handle; null is ignored.
+//
+// # Safety
+// ``handle`` must be null or come from access-json, and must not be used again.
+void access_json_handle_free(struct AccessJsonHandle *handle);
+
+// Run ``query`` against the root behind ``handle``, and put the result (or an error message) in ``out``.
+//
+// # Safety
+// ``handle`` must come from access-json, ``query`` must be a NUL-terminated string, and ``out`` must be writable.
+// Free whatever ends up in ``out`` with ``access_json_buffer_free``.
+enum AccessJsonStatus access_json_query(const struct AccessJsonHandle *handle,
+                                        const char *query,
+                                        struct AccessJsonBuffer *out);
+
+// Free a buffer that access-json filled in, and reset it to empty; calling it twice is fine.
+//
+// # Safety
+// ``buffer`` must be null or point to a buffer that access-json filled in.
+void access_json_buffer_free(struct AccessJsonBuffer *buffer);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* ACCESS_JSON_H */
@@ -0,0 +1,330 @@
+//! A C API for running queries against a serializable root.
+//!
+//! The matching declarations are in ``include/access_json.h``, which ``build.rs`` generates with cbindgen.
+//! Run ``ACCESS_JSON_WRITE_HEADER=1 cargo build -p access-json-ffi`` after changing this file; ``test_header`` fails until you do.
+//! Rust code registers a root with ``AccessJsonHandle::new``, and hands the pointer to C;
+//! C code can also make a handle from JSON text with ``access_json_handle_from_json``.
+//! Nothing here panics across the boundary: a panic comes back as ``AccessJsonStatus::Panic``.
+use access_json::{AnySerializable, JSONQuery, QueryExecErr, QueryParseErr};
+use std::ffi::CStr;
+use std::os::raw::c_char;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+
+/// What ``access_json_query`` did; anything but ``Ok`` and ``NoMatch`` leaves a message in the output buffer.
+///
+/// Codes from 100 to 199 are ``QueryParseErr`` variants, and codes from 200 to 299 are ``QueryExecErr`` variants.
+#[repr(C)]
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum AccessJsonStatus {
+    /// The buffer holds the result as JSON.
+    Ok = 0,
+    /// The query found nothing; the buffer is empty.
+    NoMatch = 1,
+    /// A pointer was null, or a string was not UTF-8.
+    BadArgument = 2,
+    /// Something went wrong inside access-json; please report it!
+    Panic = 3,
+    ParseBadCharacter = 100,
+    ParseMissingField = 101,
+    ParseMissingNumber = 102,
+    ParseBadArray = 103,
+    ParseBadField = 104,
+    ParseUnexpectedEof = 105,
+    ParseUnexpected = 106,
+    ParseBadIndex = 107,
+    ParseBadSlice = 108,
+    ParseBadFilter = 109,
+    ParseBadString = 110,
+    ParseUnsupported = 111,
+    ParseBadEscape = 112,
+    ExecEmptyQuery = 200,
+    ExecInternalError = 201,
+    ExecSerialization = 202,
+    ExecDeserialization = 203,
+    ExecWrongLength = 204,
+    ExecBadMapKey = 205,
+    ExecForbidden = 206,
+    ExecUnsupported = 207,
+}
+
+impl From<&QueryParseErr> for AccessJsonStatus {
+    fn from(err: &QueryParseErr) -> Self {
+        match err {
+            QueryParseErr::BadCharacter(_) => AccessJsonStatus::ParseBadCharacter,
+            QueryParseErr::MissingField(_) => AccessJsonStatus::ParseMissingField,
+            QueryParseErr::MissingNumber(_) => AccessJsonStatus::ParseMissingNumber,
+            QueryParseErr::BadArray(_) => AccessJsonStatus::ParseBadArray,
+            QueryParseErr::BadField(_) => AccessJsonStatus::ParseBadField,
+            QueryParseErr::UnexpectedEOF(_) => AccessJsonStatus::ParseUnexpectedEof,
+            QueryParseErr::Unexpected(_, _) => AccessJsonStatus::ParseUnexpected,
+            QueryParseErr::BadIndex(_, _) => AccessJsonStatus::ParseBadIndex,
+            QueryParseErr::BadSlice(_) => AccessJsonStatus::ParseBadSlice,
+            QueryParseErr::BadFilter(_) => AccessJsonStatus::ParseBadFilter,
+            QueryParseErr::BadString(_) => AccessJsonStatus::ParseBadString,
+            QueryParseErr::Unsupported(_, _) => AccessJsonStatus::ParseUnsupported,
+            QueryParseErr::BadEscape(_) => AccessJsonStatus::ParseBadEscape,
+        }
+    }
+}
+
+impl From<&QueryExecErr> for AccessJsonStatus {
+    fn from(err: &QueryExecErr) -> Self {
+        match err {
+            QueryExecErr::EmptyQuery => AccessJsonStatus::ExecEmptyQuery,
+            QueryExecErr::InternalError(_) => AccessJsonStatus::ExecInternalError,
+            QueryExecErr::Serialization(_) => AccessJsonStatus::ExecSerialization,
+            QueryExecErr::Deserialization(_, _) => AccessJsonStatus::ExecDeserialization,
+            QueryExecErr::WrongLength { .. } => AccessJsonStatus::ExecWrongLength,
+            QueryExecErr::BadMapKey(_) => AccessJsonStatus::ExecBadMapKey,
+            QueryExecErr::Forbidden(_) => AccessJsonStatus::ExecForbidden,
+            QueryExecErr::Unsupported(_) => AccessJsonStatus::ExecUnsupported,
+        }
+    }
+}
+
+/// An opaque, read-only root to run queries against.
+pub struct AccessJsonHandle {
+    root: Box<dyn AnySerializable + Send + Sync>,
+}
+
+impl AccessJsonHandle {
+    /// Register a root and give C a pointer to it; free it with ``access_json_handle_free``.
+    ///
+    /// The root is serialized again for every query, so a type whose ``Serialize`` reads shared state gives live answers.
+    pub fn new<T>(root: T) -> *mut AccessJsonHandle
+    where
+        T: serde::Serialize + Send + Sync + 'static,
+    {
+        Box::into_raw(Box::new(AccessJsonHandle {
+            root: Box::new(root),
+        }))
+    }
+}
+
+/// UTF-8 text owned by access-json; free it with ``access_json_buffer_free``.
+///
+/// ``data`` is followed by a NUL byte that ``len`` does not count, so it can be used as a C string too.
+#[repr(C)]
+pub struct AccessJsonBuffer {
+    pub data: *mut c_char,
+    pub len: usize,
+}
+
+impl AccessJsonBuffer {
+    fn empty() -> Self {
+        Self {
+            data: std::ptr::null_mut(),
+            len: 0,
+        }
+    }
+    fn from_string(text: String) -> Self {
+        let len = text.len();
+        let mut bytes = text.into_bytes();
+        bytes.push(0);
+        let data = Box::into_raw(bytes.into_boxed_slice()) as *mut c_char;
+        Self { data, len }
+    }
+}
+
+/// Read a C string argument, if it is there and valid.
+unsafe fn read_str<'a>(text: *const c_char) -> Option<&'a str> {
+    if text.is_null() {
+        return None;
+    }
+    CStr::from_ptr(text).to_str().ok()
+}
+
+/// Make a handle for a JSON document, e.g., from C code that has no Rust root of its own.
+///
+/// Returns null if ``json`` is null, not UTF-8, or not JSON.
+///
+/// # Safety
+/// ``json`` must be null or point to ``len`` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn access_json_handle_from_json(
+    json: *const c_char,
+    len: usize,
+) -> *mut AccessJsonHandle {
+    if json.is_null() {
+        return std::ptr::null_mut();
+    }
+    let bytes = std::slice::from_raw_parts(json as *const u8, len);
+    match catch_unwind(|| serde_json::from_slice::<serde_json::Value>(bytes)) {
+        Ok(Ok(root)) => AccessJsonHandle::new(root),
+        _ => std::ptr::null_mut(),
+    }
+}
+
+/// Free a handle; null is ignored.
+///
+/// # Safety
+/// ``handle`` must be null or come from access-json, and must not be used again.
+#[no_mangle]
+pub unsafe extern "C" fn access_json_handle_free(handle: *mut AccessJsonHandle) {
+    if !handle.is_null() {
+        drop(Box::from_raw(handle));
+    }
+}
+
+/// Run ``query`` against the root behind ``handle``, and put the result (or an error message) in ``out``.
+///
+/// # Safety
+/// ``handle`` must come from access-json, ``query`` must be a NUL-terminated string, and ``out`` must be writable.
+/// Free whatever ends up in ``out`` with ``access_json_buffer_free``.
+#[no_mangle]
+pub unsafe extern "C" fn access_json_query(
+    handle: *const AccessJsonHandle,
+    query: *const c_char,
+    out: *mut AccessJsonBuffer,
+) -> AccessJsonStatus {
+    if out.is_null() {
+        return AccessJsonStatus::BadArgument;
+    }
+    let (status, text) = match (handle.as_ref(), read_str(query)) {
+        (Some(handle), Some(query)) => {
+            let result = catch_unwind(AssertUnwindSafe(|| run_query(handle, query)));
+            result.unwrap_or_else(|_| (AccessJsonStatus::Panic, Some("panic".to_string())))
+        }
+        (None, _) => (AccessJsonStatus::BadArgument, Some("null handle".into())),
+        (_, None) => (
+            AccessJsonStatus::BadArgument,
+            Some("query is null or not UTF-8".into()),
+        ),
+    };
+    *out = match text {
+        Some(text) => AccessJsonBuffer::from_string(text),
+        None => AccessJsonBuffer::empty(),
+    };
+    status
+}
+
+fn run_query(handle: &AccessJsonHandle, query: &str) -> (AccessJsonStatus, Option<String>) {
+    let query = match JSONQuery::parse(query) {
+        Ok(query) => query,
+        Err(err) => return ((&err).into(), Some(err.to_string())),
+    };
+    match query.execute(handle.root.as_ref()) {
+        Ok(Some(found)) => (AccessJsonStatus::Ok, Some(found.to_string())),
+        Ok(None) => (AccessJsonStatus::NoMatch, None),
+        Err(err) => ((&err).into(), Some(err.to_string())),
+    }
+}
+
+/// Free a buffer that access-json filled in, and reset it to empty; calling it twice is fine.
+///
+/// # Safety
+/// ``buffer`` must be null or point to a buffer that access-json filled in.
+#[no_mangle]
+pub unsafe extern "C" fn access_json_buffer_free(buffer: *mut AccessJsonBuffer) {
+    if let Some(buffer) = buffer.as_mut() {
+        if !buffer.data.is_null() {
+            let bytes = std::slice::from_raw_parts_mut(buffer.data as *mut u8, buffer.len + 1);
+            drop(Box::from_raw(bytes as *mut [u8]));
+        }
+        *buffer = AccessJsonBuffer::empty();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::ffi::CString;
+
+    /// Run a query through the C API, and give back the status and text.
+    fn query(handle: *const AccessJsonHandle, query: &str) -> (AccessJsonStatus, Option<String>) {
+        let query = CString::new(query).unwrap();
+        let mut out = AccessJsonBuffer::empty();
+        unsafe {
+            let status = access_json_query(handle, query.as_ptr(), &mut out);
+            let text = if out.data.is_null() {
+                None
+            } else {
+                let bytes = std::slice::from_raw_parts(out.data as *const u8, out.len);
+                Some(String::from_utf8(bytes.to_vec()).unwrap())
+            };
+            access_json_buffer_free(&mut out);
+            access_json_buffer_free(&mut out);
+            assert!(out.data.is_null());
+            (status, text)
+        }
+    }
+
+    /// A root that cannot be serialized.
+    struct Broken;
+
+    impl serde::Serialize for Broken {
+        fn serialize<S: serde::Serializer>(&self, _: S) -> Result<S::Ok, S::Error> {
+            Err(serde::ser::Error::custom("broken"))
+        }
+    }
+
+    #[test]
+    fn test_header() {
+        let generated = include_str!(concat!(env!("OUT_DIR"), "/access_json.h"));
+        let checked_in = include_str!("../include/access_json.h");
+        assert!(
+            generated == checked_in,
+            "include/access_json.h is stale; rebuild with ACCESS_JSON_WRITE_HEADER=1"
+        );
+    }
+
+    #[test]
+    fn test_c_api() {
+        let mut root = std::collections::BTreeMap::new();
+        root.insert("name", vec!["Sam", "Lee"]);
+        let handle = AccessJsonHandle::new(root);
+        assert_eq!(
+            (AccessJsonStatus::Ok, Some("\"Lee\"".to_string())),
+            query(handle, ".name[1]")
+        );
+        assert_eq!(
+            (AccessJsonStatus::Ok, Some("[\"Sam\",\"Lee\"]".to_string())),
+            query(handle, ".name")
+        );
+        assert_eq!((AccessJsonStatus::NoMatch, None), query(handle, ".age"));
+        assert_eq!(
+            (
+                AccessJsonStatus::ParseUnexpectedEof,
+                Some("UnexpectedEOF(']')".to_string())
+            ),
+            query(handle, ".name[1")
+        );
+        assert_eq!(AccessJsonStatus::ParseBadArray, query(handle, ".name[x]").0);
+        assert_eq!(
+            AccessJsonStatus::BadArgument,
+            query(std::ptr::null(), ".name").0
+        );
+        unsafe {
+            let mut out = AccessJsonBuffer::empty();
+            assert_eq!(
+                AccessJsonStatus::BadArgument,
+                access_json_query(handle, std::ptr::null(), &mut out)
+            );
+            access_json_buffer_free(&mut out);
+            access_json_handle_free(handle);
+        }
+
+        let handle = AccessJsonHandle::new(Broken);
+        assert_eq!(
+            (
+                AccessJsonStatus::ExecSerialization,
+                Some("Serialization(\"broken\")".to_string())
+            ),
+            query(handle, ".a")
+        );
+        unsafe { access_json_handle_free(handle) };
+
+        let json = r#"{"a": {"b": 3}}"#;
+        let handle =
+            unsafe { access_json_handle_from_json(json.as_ptr() as *const c_char, json.len()) };
+        assert_eq!(
+            (AccessJsonStatus::Ok, Some("3".to_string())),
+            query(handle, ".a.b")
+        );
+        unsafe {
+            access_json_handle_free(handle);
+            assert!(access_json_handle_from_json("{".as_ptr() as *const c_char, 1).is_null());
+            access_json_handle_free(std::ptr::null_mut());
+        }
+    }
+}
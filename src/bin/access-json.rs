@@ -0,0 +1,229 @@
+//! Run a query against JSON from the command line.
+//!
+//! ```text
+//! access-json [--ndjson] [--raw] [--jsonpath] QUERY [FILE]
+//! ```
+//!
+//! Exit codes follow grep: 0 if something matched, 1 if nothing did, and 2 for bad arguments, bad queries, or bad input.
+use access_json::JSONQuery;
+use serde_json::Value as JSON;
+use std::io::{BufRead, BufReader, Write};
+
+const USAGE: &str = "usage: access-json [--ndjson] [--raw] [--jsonpath] QUERY [FILE]
+
+Reads JSON from FILE (or stdin, if FILE is missing or -) and prints what QUERY finds.
+
+  --ndjson     every line of input is its own document; print one line per document that matched
+  --raw        print string results without quotes
+  --jsonpath   QUERY is JSONPath, e.g., $.items[*].name
+  -h, --help   print this message
+
+Exits with 0 if anything matched, 1 if nothing did, and 2 on errors.";
+
+#[derive(Debug, Default, PartialEq)]
+struct Args {
+    ndjson: bool,
+    raw: bool,
+    jsonpath: bool,
+    query: String,
+    file: Option<String>,
+}
+
+/// Ok(None) means the user asked for help.
+fn parse_args(args: &[String]) -> Result<Option<Args>, String> {
+    let mut parsed = Args::default();
+    let mut positional = Vec::new();
+    let mut flags = true;
+    for arg in args {
+        match arg.as_str() {
+            "--ndjson" if flags => parsed.ndjson = true,
+            "--raw" if flags => parsed.raw = true,
+            "--jsonpath" if flags => parsed.jsonpath = true,
+            "-h" | "--help" if flags => return Ok(None),
+            "--" if flags => flags = false,
+            flag if flags && flag.starts_with("--") => {
+                return Err(format!("unknown option: {}", flag));
+            }
+            _ => positional.push(arg.clone()),
+        }
+    }
+    let mut positional = positional.into_iter();
+    parsed.query = positional.next().ok_or("missing QUERY")?;
+    parsed.file = positional.next().filter(|file| file != "-");
+    if let Some(extra) = positional.next() {
+        return Err(format!("unexpected argument: {}", extra));
+    }
+    Ok(Some(parsed))
+}
+
+/// Write one result; ``pretty`` is off for NDJSON, so each result stays on one line.
+fn print(out: &mut dyn Write, found: &JSON, raw: bool, pretty: bool) -> std::io::Result<()> {
+    match found {
+        JSON::String(text) if raw => writeln!(out, "{}", text),
+        _ if pretty => writeln!(out, "{}", serde_json::to_string_pretty(found)?),
+        _ => writeln!(out, "{}", found),
+    }
+}
+
+/// Returns whether anything matched.
+fn run(args: &Args, input: &mut dyn BufRead, out: &mut dyn Write) -> Result<bool, String> {
+    let query = if args.jsonpath {
+        JSONQuery::parse_jsonpath(&args.query)
+    } else {
+        JSONQuery::parse(&args.query)
+    }
+    .map_err(|err| format!("bad query {:?}: {:?}", args.query, err))?;
+
+    let mut matched = false;
+    let mut execute = |document: &JSON, pretty: bool| -> Result<(), String> {
+        let found = query.execute(document).map_err(|err| err.to_string())?;
+        if let Some(found) = found {
+            // A query like ``.items[*].x`` gives an empty array when nothing matched.
+            if !query.is_singular() && found.as_array().is_some_and(Vec::is_empty) {
+                return Ok(());
+            }
+            matched = true;
+            print(out, &found, args.raw, pretty).map_err(|err| err.to_string())?;
+        }
+        Ok(())
+    };
+    if args.ndjson {
+        for (number, line) in input.lines().enumerate() {
+            let line = line.map_err(|err| err.to_string())?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let document: JSON = serde_json::from_str(&line)
+                .map_err(|err| format!("line {}: {}", number + 1, err))?;
+            execute(&document, false)?;
+        }
+    } else {
+        let mut text = String::new();
+        input
+            .read_to_string(&mut text)
+            .map_err(|err| err.to_string())?;
+        let document: JSON = serde_json::from_str(&text).map_err(|err| err.to_string())?;
+        execute(&document, true)?;
+    }
+    Ok(matched)
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args = match parse_args(&args) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return;
+        }
+        Err(err) => {
+            eprintln!("access-json: {}\n\n{}", err, USAGE);
+            std::process::exit(2);
+        }
+    };
+    let mut input: Box<dyn BufRead> = match &args.file {
+        None => Box::new(BufReader::new(std::io::stdin())),
+        Some(path) => match std::fs::File::open(path) {
+            Ok(file) => Box::new(BufReader::new(file)),
+            Err(err) => {
+                eprintln!("access-json: {}: {}", path, err);
+                std::process::exit(2);
+            }
+        },
+    };
+    let stdout = std::io::stdout();
+    let mut out = stdout.lock();
+    match run(&args, &mut input, &mut out) {
+        Ok(true) => {}
+        Ok(false) => std::process::exit(1),
+        Err(err) => {
+            eprintln!("access-json: {}", err);
+            std::process::exit(2);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Result<Option<Args>, String> {
+        parse_args(&args.iter().map(|arg| arg.to_string()).collect::<Vec<_>>())
+    }
+
+    fn output(flags: &[&str], input: &str) -> Result<(bool, String), String> {
+        let args = args(flags)?.unwrap();
+        let mut out = Vec::new();
+        let matched = run(&args, &mut input.as_bytes(), &mut out)?;
+        Ok((matched, String::from_utf8(out).unwrap()))
+    }
+
+    #[test]
+    fn test_parse_args() {
+        assert_eq!(
+            Ok(Some(Args {
+                raw: true,
+                query: ".a".into(),
+                file: Some("in.json".into()),
+                ..Default::default()
+            })),
+            args(&["--raw", ".a", "in.json"])
+        );
+        assert_eq!(Ok(None), args(&[".a", "--help"]));
+        assert_eq!(None, args(&[".a", "-"]).unwrap().unwrap().file);
+        assert_eq!("--raw", args(&["--", "--raw"]).unwrap().unwrap().query);
+        assert!(args(&[]).is_err());
+        assert!(args(&["--nope", ".a"]).is_err());
+        assert!(args(&[".a", "x", "y"]).is_err());
+    }
+
+    #[test]
+    fn test_run() {
+        let doc = r#"{"name": "sam", "langs": ["rust"]}"#;
+        assert_eq!(Ok((true, "\"sam\"\n".into())), output(&[".name"], doc));
+        assert_eq!(Ok((true, "sam\n".into())), output(&["--raw", ".name"], doc));
+        assert_eq!(
+            Ok((true, "[\n  \"rust\"\n]\n".into())),
+            output(&["$.langs", "--jsonpath"], doc)
+        );
+        assert_eq!(Ok((false, "".into())), output(&[".age"], doc));
+        assert_eq!(Ok((false, "".into())), output(&[".langs[*].x"], doc));
+        assert_eq!(Ok((false, "".into())), output(&[".nope,.zzz"], doc));
+        let empty = r#"{"langs": []}"#;
+        assert_eq!(
+            Ok((
+                true,
+                "[]
+"
+                .into()
+            )),
+            output(&[".langs"], empty)
+        );
+
+        let lines = "{\"a\": 1}\n\n{\"b\": 2}\n{\"a\": [3, 4]}\n";
+        assert_eq!(
+            Ok((true, "1\n[3,4]\n".into())),
+            output(&["--ndjson", ".a"], lines)
+        );
+        assert_eq!(Ok((false, "".into())), output(&["--ndjson", ".c"], lines));
+        assert_eq!(
+            Ok((false, "".into())),
+            output(&["--ndjson", ".*.zzz"], lines)
+        );
+        assert_eq!(
+            Ok((
+                true,
+                "[3,4]
+"
+                .into()
+            )),
+            output(&["--ndjson", ".a[*]"], lines)
+        );
+
+        assert!(output(&[".a["], doc).is_err());
+        assert!(output(&[".a"], "{").is_err());
+        assert!(output(&["--ndjson", ".a"], "{\"a\": 1}\nnope\n")
+            .unwrap_err()
+            .starts_with("line 2:"));
+    }
+}
@@ -0,0 +1,143 @@
+use crate::query::JSONQuery;
+use crate::query_executor::QueryExecErr;
+use serde_json::Value as JSON;
+use std::cmp::Ordering;
+
+/// A test applied by a filter to each element of a sequence, or each value of a map or struct: ``[?(.state == "failed")]``
+#[derive(Clone, PartialEq, Eq, Debug, Hash, Serialize, Deserialize)]
+pub enum Predicate {
+    /// Keep elements where the relative path finds something: ``[?(.field)]``
+    Exists(JSONQuery),
+    /// Keep elements where the comparison holds: ``[?(.field >= 3)]``
+    Compare(Operand, Comparison, Operand),
+}
+
+/// One side of a comparison inside a filter.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Operand {
+    /// A path relative to the element being tested; ``@`` is the element itself.
+    Path(JSONQuery),
+    /// Any JSON literal: a string, number, bool, or null.
+    Literal(JSON),
+}
+
+/// The comparison operators available inside a filter.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Hash, Serialize, Deserialize)]
+pub enum Comparison {
+    Eq,
+    Ne,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+// serde_json::Value is only hashable in newer releases, so hash its text instead.
+impl std::hash::Hash for Operand {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        match self {
+            Operand::Path(path) => {
+                0.hash(state);
+                path.hash(state);
+            }
+            Operand::Literal(literal) => {
+                1.hash(state);
+                literal.to_string().hash(state);
+            }
+        }
+    }
+}
+
+impl Operand {
+    fn evaluate(&self, candidate: &JSON) -> Result<Option<JSON>, QueryExecErr> {
+        match self {
+            Operand::Path(path) => path.execute(candidate),
+            Operand::Literal(literal) => Ok(Some(literal.clone())),
+        }
+    }
+}
+
+impl Comparison {
+    /// Equality works on anything; ordering only between two numbers or two strings.
+    fn test(&self, lhs: &Option<JSON>, rhs: &Option<JSON>) -> bool {
+        let ordering = match (lhs, rhs) {
+            (Some(JSON::Number(l)), Some(JSON::Number(r))) => match (l.as_f64(), r.as_f64()) {
+                (Some(l), Some(r)) => l.partial_cmp(&r),
+                _ => None,
+            },
+            (Some(JSON::String(l)), Some(JSON::String(r))) => Some(l.cmp(r)),
+            _ => None,
+        };
+        match self {
+            Comparison::Eq => ordering == Some(Ordering::Equal) || lhs == rhs,
+            Comparison::Ne => !(ordering == Some(Ordering::Equal) || lhs == rhs),
+            Comparison::Lt => ordering == Some(Ordering::Less),
+            Comparison::Le => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+            Comparison::Gt => ordering == Some(Ordering::Greater),
+            Comparison::Ge => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+        }
+    }
+}
+
+impl Predicate {
+    /// The paths, relative to each element, that this filter reads.
+    pub(crate) fn paths(&self) -> Vec<&JSONQuery> {
+        match self {
+            Predicate::Exists(path) => vec![path],
+            Predicate::Compare(lhs, _, rhs) => [lhs, rhs]
+                .iter()
+                .filter_map(|side| match side {
+                    Operand::Path(path) => Some(path),
+                    Operand::Literal(_) => None,
+                })
+                .collect(),
+        }
+    }
+    /// Decide whether a buffered sequence element or map value passes this filter.
+    pub fn test(&self, candidate: &JSON) -> Result<bool, QueryExecErr> {
+        match self {
+            Predicate::Exists(path) => Ok(match path.execute(candidate)? {
+                None => false,
+                // Wildcard paths always give us an array; it needs at least one match.
+                Some(JSON::Array(found)) if !path.is_singular() => !found.is_empty(),
+                Some(_) => true,
+            }),
+            Predicate::Compare(lhs, op, rhs) => {
+                Ok(op.test(&lhs.evaluate(candidate)?, &rhs.evaluate(candidate)?))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for Comparison {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let op = match self {
+            Comparison::Eq => "==",
+            Comparison::Ne => "!=",
+            Comparison::Lt => "<",
+            Comparison::Le => "<=",
+            Comparison::Gt => ">",
+            Comparison::Ge => ">=",
+        };
+        write!(f, "{}", op)
+    }
+}
+
+impl std::fmt::Display for Operand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Operand::Path(path) if path.elements.is_empty() => write!(f, "@"),
+            Operand::Path(path) => write!(f, "{}", path),
+            Operand::Literal(literal) => write!(f, "{}", literal),
+        }
+    }
+}
+
+impl std::fmt::Display for Predicate {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Predicate::Exists(path) => write!(f, "{}", Operand::Path(path.clone())),
+            Predicate::Compare(lhs, op, rhs) => write!(f, "{} {} {}", lhs, op, rhs),
+        }
+    }
+}
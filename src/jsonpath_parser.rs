@@ -0,0 +1,369 @@
+use crate::filter::{Comparison, Operand, Predicate};
+use crate::query::{JSONQuery, QueryElement};
+use crate::query_parser::{Parser, QueryParseErr};
+
+/// Parse an RFC 9535 JSONPath expression like ``$.items[0]['name']`` and lower it to our query elements.
+pub(crate) fn parse_jsonpath(input: &str) -> Result<Vec<QueryElement>, QueryParseErr> {
+    let mut parser = Parser::from(input);
+    if parser.peek() != Some('$') {
+        return Err(QueryParseErr::BadCharacter(0));
+    }
+    parser.consume('$')?;
+    let output = parser.jsonpath_segments()?;
+    if parser.peek().is_some() {
+        return Err(QueryParseErr::BadCharacter(parser.position));
+    }
+    Ok(output)
+}
+
+fn unsupported(position: usize, what: &str) -> QueryParseErr {
+    QueryParseErr::Unsupported(position, what.to_string())
+}
+
+/// JSONPath member-name-shorthand: letters, digits, '_' or anything non-ASCII; no leading digit.
+fn is_name_char(ch: char, first: bool) -> bool {
+    ch.is_ascii_alphabetic() || ch == '_' || !ch.is_ascii() || (!first && ch.is_ascii_digit())
+}
+
+impl Parser {
+    /// Read segments until something that is not a segment; filters stop their relative paths this way.
+    fn jsonpath_segments(&mut self) -> Result<Vec<QueryElement>, QueryParseErr> {
+        let mut output = Vec::new();
+        loop {
+            let before = self.position;
+            self.skip_whitespace();
+            match (self.peek(), self.peek_ahead(1)) {
+                (Some('.'), Some('.')) => {
+                    self.consume('.')?;
+                    self.consume('.')?;
+                    output.push(QueryElement::RecursiveDescent);
+                    if self.peek() == Some('[') {
+                        output.push(self.jsonpath_bracket()?);
+                    } else {
+                        output.push(self.jsonpath_shorthand()?);
+                    }
+                }
+                (Some('.'), _) => {
+                    self.consume('.')?;
+                    output.push(self.jsonpath_shorthand()?);
+                }
+                (Some('['), _) => output.push(self.jsonpath_bracket()?),
+                _ => {
+                    // Whitespace after the last segment belongs to whatever comes next.
+                    self.position = before;
+                    return Ok(output);
+                }
+            }
+        }
+    }
+    /// Either ``*`` or a member name, after a "." or "..".
+    fn jsonpath_shorthand(&mut self) -> Result<QueryElement, QueryParseErr> {
+        if self.peek() == Some('*') {
+            self.consume('*')?;
+            return Ok(QueryElement::AnyField);
+        }
+        let mut name = String::new();
+        while let Some(ch) = self.peek() {
+            if !is_name_char(ch, name.is_empty()) {
+                break;
+            }
+            self.advance();
+            name.push(ch);
+        }
+        if name.is_empty() {
+            Err(QueryParseErr::MissingField(self.position))
+        } else {
+            Ok(QueryElement::Field(name))
+        }
+    }
+    /// A bracketed selection: ``['name']``, ``[0]``, ``[1:3]``, ``[*]`` or ``[?@.x > 1]``.
+    ///
+    /// Several selectors like ``['a', 'b']`` become a Union; we give all matches of each selector in turn.
+    fn jsonpath_bracket(&mut self) -> Result<QueryElement, QueryParseErr> {
+        self.consume('[')?;
+        self.skip_whitespace();
+        let mut selectors = vec![self.jsonpath_selector()?];
+        self.skip_whitespace();
+        while self.peek() == Some(',') {
+            self.consume(',')?;
+            self.skip_whitespace();
+            selectors.push(self.jsonpath_selector()?);
+            self.skip_whitespace();
+        }
+        self.consume(']')?;
+        if selectors.len() == 1 {
+            Ok(selectors.remove(0))
+        } else {
+            Ok(QueryElement::Union(
+                selectors
+                    .into_iter()
+                    .map(|selector| JSONQuery::new(vec![selector]))
+                    .collect(),
+            ))
+        }
+    }
+    fn jsonpath_selector(&mut self) -> Result<QueryElement, QueryParseErr> {
+        let start = self.position;
+        match self.peek() {
+            Some(quote @ '\'') | Some(quote @ '"') => {
+                Ok(QueryElement::Field(self.read_quoted(quote)?))
+            }
+            Some('*') => {
+                self.consume('*')?;
+                Ok(QueryElement::AnyItem)
+            }
+            Some('?') => {
+                self.consume('?')?;
+                self.skip_whitespace();
+                Ok(QueryElement::Filter(self.jsonpath_filter()?))
+            }
+            Some(ch) if ch == '-' || ch == ':' || ch.is_ascii_digit() => {
+                let first = self.jsonpath_int()?;
+                self.skip_whitespace();
+                if self.peek() != Some(':') {
+                    return match first {
+                        Some(index) if index < 0 => {
+                            Ok(QueryElement::ArrayItemFromEnd(index.unsigned_abs()))
+                        }
+                        Some(index) => Ok(QueryElement::ArrayItem(index as usize)),
+                        None => Err(QueryParseErr::MissingNumber(start)),
+                    };
+                }
+                self.consume(':')?;
+                self.skip_whitespace();
+                let end = self.jsonpath_int()?;
+                self.skip_whitespace();
+                let mut step = 1;
+                if self.peek() == Some(':') {
+                    self.consume(':')?;
+                    self.skip_whitespace();
+                    step = match self.jsonpath_int()? {
+                        Some(step) if step < 1 => {
+                            return Err(unsupported(start, "slices with a step below 1"))
+                        }
+                        Some(step) => step as usize,
+                        None => 1,
+                    };
+                }
+                Ok(QueryElement::slice(first, end, step))
+            }
+            _ => Err(QueryParseErr::BadArray(start)),
+        }
+    }
+    /// An optional integer: "0" or an optional '-' and digits without a leading zero.
+    fn jsonpath_int(&mut self) -> Result<Option<isize>, QueryParseErr> {
+        let start = self.position;
+        let mut digits = String::new();
+        if self.peek() == Some('-') {
+            self.advance();
+            digits.push('-');
+        }
+        while let Some(ch) = self.peek().filter(char::is_ascii_digit) {
+            self.advance();
+            digits.push(ch);
+        }
+        let leading_zero = digits.trim_start_matches('-').starts_with('0') && digits.len() > 1;
+        if digits.is_empty() {
+            Ok(None)
+        } else if leading_zero || digits == "-" {
+            Err(QueryParseErr::BadArray(start))
+        } else {
+            digits
+                .parse::<isize>()
+                .map(Some)
+                .map_err(|e| QueryParseErr::BadIndex(start, e.to_string()))
+        }
+    }
+    /// The expression after "?": an existence test or a single comparison, optionally in parentheses.
+    fn jsonpath_filter(&mut self) -> Result<Predicate, QueryParseErr> {
+        let start = self.position;
+        if self.peek() == Some('(') {
+            self.consume('(')?;
+            self.skip_whitespace();
+            let predicate = self.jsonpath_filter()?;
+            self.skip_whitespace();
+            self.consume(')')?;
+            return Ok(predicate);
+        } else if self.peek() == Some('!') {
+            return Err(unsupported(start, "negation in filters"));
+        }
+        let lhs = self.jsonpath_operand()?;
+        self.skip_whitespace();
+        let predicate = match self.peek() {
+            Some('=') | Some('!') | Some('<') | Some('>') => {
+                let op = self.jsonpath_comparison()?;
+                self.skip_whitespace();
+                let rhs = self.jsonpath_operand()?;
+                Predicate::Compare(lhs, op, rhs)
+            }
+            _ => match lhs {
+                Operand::Path(path) => Predicate::Exists(path),
+                Operand::Literal(_) => return Err(QueryParseErr::BadFilter(start)),
+            },
+        };
+        self.skip_whitespace();
+        match (self.peek(), self.peek_ahead(1)) {
+            (Some('&'), Some('&')) | (Some('|'), Some('|')) => {
+                Err(unsupported(self.position, "&& and || in filters"))
+            }
+            _ => Ok(predicate),
+        }
+    }
+    fn jsonpath_comparison(&mut self) -> Result<Comparison, QueryParseErr> {
+        let start = self.position;
+        let op = match (self.advance(), self.peek()) {
+            (Some('='), Some('=')) => Comparison::Eq,
+            (Some('!'), Some('=')) => Comparison::Ne,
+            (Some('<'), Some('=')) => Comparison::Le,
+            (Some('>'), Some('=')) => Comparison::Ge,
+            (Some('<'), _) => return Ok(Comparison::Lt),
+            (Some('>'), _) => return Ok(Comparison::Gt),
+            _ => return Err(QueryParseErr::BadFilter(start)),
+        };
+        self.advance();
+        Ok(op)
+    }
+    /// A relative query starting with "@", or a literal; absolute queries and functions are unsupported.
+    fn jsonpath_operand(&mut self) -> Result<Operand, QueryParseErr> {
+        let start = self.position;
+        match self.peek() {
+            Some('@') => {
+                self.consume('@')?;
+                Ok(Operand::Path(JSONQuery::new(self.jsonpath_segments()?)))
+            }
+            Some('$') => Err(unsupported(start, "absolute queries in filters")),
+            Some(quote @ '\'') | Some(quote @ '"') => {
+                Ok(Operand::Literal(self.read_quoted(quote)?.into()))
+            }
+            Some(ch) if ch == '-' || ch.is_ascii_alphanumeric() => {
+                while self
+                    .peek()
+                    .is_some_and(|ch| ch.is_ascii_alphanumeric() || "+-._".contains(ch))
+                {
+                    self.advance();
+                }
+                if self.peek() == Some('(') {
+                    return Err(unsupported(start, "functions in filters"));
+                }
+                self.read_literal(start)
+            }
+            _ => Err(QueryParseErr::BadFilter(start)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::parse_jsonpath;
+    use crate::filter::{Comparison, Operand, Predicate};
+    use crate::query::QueryElement as Q;
+    use crate::query_parser::QueryParseErr;
+    use crate::JSONQuery;
+
+    #[test]
+    fn test_jsonpath_segments() {
+        assert_eq!(parse_jsonpath("$").unwrap(), vec![]);
+        assert_eq!(
+            parse_jsonpath("$.store.book[0]['title']").unwrap(),
+            vec![
+                Q::field("store"),
+                Q::field("book"),
+                Q::array_item(0),
+                Q::field("title")
+            ]
+        );
+        assert_eq!(
+            parse_jsonpath(r#"$[ "a b" ][-1][ 1 : -1 : 2 ][:].*[*]..ünï..['x\'y']..*"#).unwrap(),
+            vec![
+                Q::field("a b"),
+                Q::array_item_from_end(1),
+                Q::slice(Some(1), Some(-1), 2),
+                Q::slice(None, None, 1),
+                Q::any_field(),
+                Q::any_item(),
+                Q::recursive_descent(),
+                Q::field("ünï"),
+                Q::recursive_descent(),
+                Q::field("x'y"),
+                Q::recursive_descent(),
+                Q::any_field(),
+            ]
+        );
+        assert_eq!(
+            parse_jsonpath("$..['a', 0]").unwrap(),
+            vec![
+                Q::recursive_descent(),
+                Q::union(vec![
+                    JSONQuery::single(Q::field("a")),
+                    JSONQuery::single(Q::array_item(0))
+                ]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_jsonpath_filters() {
+        assert_eq!(
+            parse_jsonpath("$.jobs[?@.state == 'failed'].id").unwrap(),
+            vec![
+                Q::field("jobs"),
+                Q::filter(Predicate::Compare(
+                    Operand::Path(JSONQuery::parse(".state").unwrap()),
+                    Comparison::Eq,
+                    Operand::Literal("failed".into())
+                )),
+                Q::field("id")
+            ]
+        );
+        assert_eq!(
+            parse_jsonpath("$[?(@['a'][0])]").unwrap(),
+            vec![Q::filter(Predicate::Exists(
+                JSONQuery::parse(".a[0]").unwrap()
+            ))]
+        );
+        assert_eq!(
+            parse_jsonpath("$[?@ >= -1.5]").unwrap(),
+            JSONQuery::parse("[?(@ >= -1.5)]").unwrap().elements
+        );
+    }
+
+    #[test]
+    fn test_jsonpath_errors() {
+        assert_eq!(
+            parse_jsonpath(".a").unwrap_err(),
+            QueryParseErr::BadCharacter(0)
+        );
+        assert_eq!(
+            parse_jsonpath("$.a b").unwrap_err(),
+            QueryParseErr::BadCharacter(3)
+        );
+        assert_eq!(
+            parse_jsonpath("$.").unwrap_err(),
+            QueryParseErr::MissingField(2)
+        );
+        assert_eq!(
+            parse_jsonpath("$[01]").unwrap_err(),
+            QueryParseErr::BadArray(2)
+        );
+        assert_eq!(
+            parse_jsonpath("$[::-1]").unwrap_err(),
+            QueryParseErr::Unsupported(2, "slices with a step below 1".into())
+        );
+        assert_eq!(
+            parse_jsonpath("$[?@.a && @.b]").unwrap_err(),
+            QueryParseErr::Unsupported(7, "&& and || in filters".into())
+        );
+        assert_eq!(
+            parse_jsonpath("$[?!@.a]").unwrap_err(),
+            QueryParseErr::Unsupported(3, "negation in filters".into())
+        );
+        assert_eq!(
+            parse_jsonpath("$[?length(@) > 1]").unwrap_err(),
+            QueryParseErr::Unsupported(3, "functions in filters".into())
+        );
+        assert_eq!(
+            parse_jsonpath("$[?@.a == $.b]").unwrap_err(),
+            QueryParseErr::Unsupported(10, "absolute queries in filters".into())
+        );
+    }
+}
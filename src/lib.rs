@@ -46,6 +46,10 @@
 //! let expected = serde_json::to_value(&true)?;
 //! assert_eq!(Some(expected), output);
 //!
+//! // Negative indices count back from the end of an array or vec.
+//! let output = JSONQuery::parse(".items[-1].whatever")?.execute(&data)?;
+//! assert_eq!(Some(true.into()), output);
+//!
 //! // We can have results be of any-size sub-tree, e.g., a whole array or vec.
 //! let output = JSONQuery::parse(".items[0].array")?.execute(&data)?;
 //! let expected = serde_json::to_value(&vec![3,2,1])?;
@@ -54,6 +58,56 @@
 //! # }
 //! ```
 //!
+//! ## Wildcards collect every match into an array
+//!
+//! ```
+//! use access_json::JSONQuery;
+//! use serde_json::{self, json, Value};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let data: Value = serde_json::from_str(r#"{
+//!    "items": [{"name": "a"}, {"name": "b", "x": 7}]
+//! }"#)?;
+//!
+//! // ``[*]`` (or ``.*``) visits every element of a sequence and every field of a map or struct.
+//! let output = JSONQuery::parse(".items[*].name")?.execute(&data)?;
+//! assert_eq!(Some(json!(["a", "b"])), output);
+//! let output = JSONQuery::parse(".items[1].*")?.execute(&data)?;
+//! assert_eq!(Some(json!(["b", 7])), output);
+//!
+//! // ``..`` finds what follows it at any depth.
+//! let output = JSONQuery::parse("..x")?.execute(&data)?;
+//! assert_eq!(Some(json!([7])), output);
+//!
+//! // Slices like ``[1:]`` and filters like ``[?(.x > 3)]`` select by position or by content.
+//! let output = JSONQuery::parse(".items[?(.x > 3)].name")?.execute(&data)?;
+//! assert_eq!(Some(json!(["b"])), output);
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! ## Pick several things at once
+//!
+//! ```
+//! use access_json::JSONQuery;
+//! use serde_json::{self, json, Value};
+//!
+//! # fn main() -> Result<(), Box<dyn std::error::Error>> {
+//! let data: Value = serde_json::from_str(r#"{
+//!    "items": [{"name": "a", "x": 1}, {"name": "b", "x": 7}]
+//! }"#)?;
+//!
+//! // Commas give the matches of each path, in order.
+//! let output = JSONQuery::parse(".items[0].name, .items[1].x")?.execute(&data)?;
+//! assert_eq!(Some(json!(["a", 7])), output);
+//!
+//! // Braces build a new object for every location the path before them finds.
+//! let output = JSONQuery::parse(".items[*]{id: .name, value: .x}")?.execute(&data)?;
+//! assert_eq!(Some(json!([{"id": "a", "value": 1}, {"id": "b", "value": 7}])), output);
+//! # Ok(())
+//! # }
+//! ```
+//!
 //! ## Just ``#[derive(Serialize)]`` to query any struct or enum:
 //!
 //! ```
@@ -87,20 +141,44 @@ extern crate serde_derive;
 
 pub use erased_serde::Serialize as AnySerializable;
 
+pub mod filter;
+pub mod jsonpath_parser;
+pub mod paths;
+pub mod policy;
+#[cfg(feature = "pyo3")]
+pub mod python;
 pub mod query;
 pub mod query_executor;
 pub mod query_parser;
+pub mod query_set;
+pub mod redact;
+pub mod schema;
+#[cfg(feature = "wasm-bindgen")]
+pub mod wasm;
 
+#[doc(inline)]
+pub use paths::{list_paths, JSONType, ListOptions};
+#[doc(inline)]
+pub use policy::QueryPolicy;
 #[doc(inline)]
 pub use query::JSONQuery;
 #[doc(inline)]
-pub use query_executor::QueryExecErr;
+pub use query_executor::{BytesFormat, ExecOptions, QueryExecErr};
 #[doc(inline)]
 pub use query_parser::QueryParseErr;
+#[doc(inline)]
+pub use query_set::QuerySet;
+#[doc(inline)]
+pub use redact::{redact, Redaction};
+#[doc(inline)]
+pub use schema::{infer_schema, Schema};
 
 #[cfg(test)]
 mod tests {
     use super::query::*;
+    use super::AnySerializable;
+    use super::{infer_schema, list_paths, redact, ListOptions, QueryPolicy, Redaction, Schema};
+    use super::{BytesFormat, ExecOptions, QueryExecErr, QuerySet};
     use serde_json::Value as JV;
     use std::collections::HashMap;
 
@@ -118,6 +196,50 @@ mod tests {
         assert_eq!(found, Some(JV::Number(7.into())));
     }
 
+    #[test]
+    fn test_query_quoted_keys() {
+        let mut data: HashMap<&str, usize> = HashMap::default();
+        data.insert("a.b", 1);
+        data.insert("my key", 2);
+        data.insert("x[0]", 3);
+        for (query, expected) in &[(r#"."a.b""#, 1), (r#"["my key"]"#, 2), (r#"."x[0]""#, 3)] {
+            let found = JSONQuery::parse(query).unwrap().execute(&data).unwrap();
+            assert_eq!(found, Some(JV::Number((*expected).into())));
+        }
+    }
+
+    #[test]
+    fn test_numeric_keys() {
+        let data = serde_json::json!({"0": "zero", "1": ["a", "b"], "01": "padded"});
+        let pointer = |p: &str| JSONQuery::from_json_pointer(p).unwrap().execute(&data);
+        // A JSON Pointer token is an index or a key, depending on what it finds.
+        assert_eq!(Ok(Some(JV::from("zero"))), pointer("/0"));
+        assert_eq!(Ok(Some(JV::from("b"))), pointer("/1/1"));
+        assert_eq!(Ok(Some(JV::from("padded"))), pointer("/01"));
+        assert_eq!(Ok(None), pointer("/2"));
+        // Native and JSONPath indices never select object members.
+        let found = JSONQuery::parse("[1][0],.*[1]").unwrap().execute(&data);
+        assert_eq!(Ok(Some(serde_json::json!(["b"]))), found);
+        let data = serde_json::json!({"0": 5, "arr": [10, 20]});
+        let found = JSONQuery::parse("..[0]").unwrap().execute(&data);
+        assert_eq!(Ok(Some(serde_json::json!([10]))), found);
+        let found = JSONQuery::parse_jsonpath("$..[0]").unwrap().execute(&data);
+        assert_eq!(Ok(Some(serde_json::json!([10]))), found);
+        assert_eq!(Ok(None), JSONQuery::parse("[0]").unwrap().execute(&data));
+        let pointer = |p: &str| JSONQuery::from_json_pointer(p).unwrap().execute(&data);
+        assert_eq!(Ok(Some(JV::from(5))), pointer("/0"));
+        assert_eq!(Ok(Some(JV::from(20))), pointer("/arr/1"));
+
+        let mut ids: HashMap<u64, &str> = HashMap::default();
+        ids.insert(7, "seven");
+        ids.insert(12345, "big");
+        let found = JSONQuery::from_json_pointer("/12345")
+            .unwrap()
+            .execute(&ids)
+            .unwrap();
+        assert_eq!(Some(JV::from("big")), found);
+    }
+
     #[test]
     fn test_query_vec() {
         let data = vec![0, 1, 2, 3, 4, 5];
@@ -133,6 +255,31 @@ mod tests {
         assert_eq!(None, found);
     }
 
+    #[test]
+    fn test_query_vec_from_end() {
+        let data = vec![0, 1, 2, 3, 4, 5];
+
+        for i in 1..=data.len() {
+            let elem_q = JSONQuery::single(QueryElement::array_item_from_end(i));
+            let found = elem_q.execute(&data).unwrap().unwrap();
+            assert_eq!(found, (JV::Number((data.len() - i).into())));
+        }
+
+        let missing_q = JSONQuery::single(QueryElement::array_item_from_end(7));
+        let found = missing_q.execute(&data).unwrap();
+        assert_eq!(None, found);
+
+        let log = vec![Point(1, 2), Point(3, 4)];
+        assert_eq!(
+            3,
+            JSONQuery::parse("[-1][0]")
+                .unwrap()
+                .execute(&log)
+                .unwrap()
+                .unwrap()
+        );
+    }
+
     #[test]
     fn test_tuple() {
         let point = (17, 39);
@@ -336,31 +483,1184 @@ mod tests {
         );
     }
 
-    // tuple-struct
-    #[derive(Serialize)]
-    struct Point(u32, u32);
+    #[test]
+    fn test_enum_variant_results() {
+        let data = vec![Pet::Cat { lives: 9 }, Pet::Digits(7, 5, 6)];
+        assert_eq!(
+            serde_json::json!({"lives": 9}),
+            JSONQuery::parse("[0].Cat")
+                .unwrap()
+                .execute(&data)
+                .unwrap()
+                .unwrap()
+        );
+        assert_eq!(
+            serde_json::to_value(&data).unwrap(),
+            JSONQuery::parse("")
+                .unwrap()
+                .execute(&data)
+                .unwrap()
+                .unwrap()
+        );
+    }
 
     #[test]
-    fn test_tuple_struct() {
-        let data = vec![Point(1, 2), Point(3, 4)];
+    fn test_wildcard_fields() {
+        let data = NestedStructs {
+            dog: Example {
+                name: "Buddy".into(),
+                age: 14,
+                favorites: vec!["walks".into()],
+            },
+            truthiness: false,
+            score: -77,
+        };
         assert_eq!(
-            serde_json::to_value(vec![1, 2]).unwrap(),
-            JSONQuery::parse("[0]")
+            serde_json::json!(["Buddy", 14, ["walks"]]),
+            JSONQuery::parse(".dog.*")
                 .unwrap()
                 .execute(&data)
                 .unwrap()
                 .unwrap()
         );
         assert_eq!(
-            4,
-            JSONQuery::parse("[1][1]")
+            serde_json::json!([]),
+            JSONQuery::parse(".*.missing")
+                .unwrap()
+                .execute(&data)
+                .unwrap()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_wildcard_items() {
+        let data: JV = serde_json::from_str(
+            r#"{"items": [{"name": "a", "x": 1}, {"x": 2}, {"name": "c", "y": {"z": 3}}]}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            serde_json::json!(["a", "c"]),
+            JSONQuery::parse(".items[*].name")
+                .unwrap()
+                .execute(&data)
+                .unwrap()
+                .unwrap()
+        );
+        assert_eq!(
+            serde_json::json!([{"z": 3}]),
+            JSONQuery::parse(".items[*].y")
+                .unwrap()
+                .execute(&data)
+                .unwrap()
+                .unwrap()
+        );
+        assert_eq!(
+            serde_json::json!([[3, 4], [1, 2]]),
+            JSONQuery::parse("[*]")
+                .unwrap()
+                .execute(&vec![Point(3, 4), Point(1, 2)])
+                .unwrap()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_recursive_descent() {
+        let data: JV = serde_json::from_str(
+            r#"{"timeout": 1, "db": {"timeout": 2, "replicas": [{"timeout": {"timeout": 3}}]}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            serde_json::json!([{"timeout": 3}, 3, 2, 1]),
+            JSONQuery::parse("..timeout")
+                .unwrap()
+                .execute(&data)
+                .unwrap()
+                .unwrap()
+        );
+        assert_eq!(
+            serde_json::json!([{"timeout": 3}]),
+            JSONQuery::parse(".db..[0].timeout")
+                .unwrap()
+                .execute(&data)
+                .unwrap()
+                .unwrap()
+        );
+        assert_eq!(
+            serde_json::json!([]),
+            JSONQuery::parse("..missing")
+                .unwrap()
+                .execute(&data)
+                .unwrap()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_recursive_descent_enums() {
+        let buddy = Example {
+            name: "Buddy".into(),
+            age: 14,
+            favorites: vec![],
+        };
+        let data = vec![Pet::Dog(buddy.clone()), Pet::Cat { lives: 9 }];
+        assert_eq!(
+            serde_json::json!(["Buddy"]),
+            JSONQuery::parse("..name")
+                .unwrap()
+                .execute(&data)
+                .unwrap()
+                .unwrap()
+        );
+        assert_eq!(
+            serde_json::json!([{ "Dog": buddy }, {"Cat": {"lives": 9}}]),
+            JSONQuery::parse("[*]")
+                .unwrap()
+                .execute(&data)
+                .unwrap()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_slices() {
+        let data: Vec<usize> = (0..10).collect();
+        let run = |q: &str| {
+            JSONQuery::parse(q)
+                .unwrap()
+                .execute(&data)
+                .unwrap()
+                .unwrap()
+        };
+        assert_eq!(serde_json::json!([0, 1, 2]), run("[:3]"));
+        assert_eq!(serde_json::json!([1, 4, 7]), run("[1:8:3]"));
+        assert_eq!(serde_json::json!([8, 9]), run("[-2:]"));
+        assert_eq!(serde_json::json!([0, 2, 4, 6]), run("[:-3:2]"));
+        assert_eq!(serde_json::json!([]), run("[20:]"));
+        assert_eq!(serde_json::json!([]), run("[5:2]"));
+
+        let nested = vec![Point(1, 2), Point(3, 4), Point(5, 6)];
+        assert_eq!(
+            serde_json::json!([2, 6]),
+            JSONQuery::parse("[::2][1]")
+                .unwrap()
+                .execute(&nested)
+                .unwrap()
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_filters() {
+        let data: JV = serde_json::from_str(
+            r#"{"jobs": [
+                {"id": 1, "state": "failed", "tries": 3},
+                {"id": 2, "state": "ok", "tries": 1},
+                {"id": 3, "state": "failed", "tries": 1, "owner": {"name": "x"}}
+            ]}"#,
+        )
+        .unwrap();
+        let run = |q: &str| {
+            JSONQuery::parse(q)
+                .unwrap()
+                .execute(&data)
+                .unwrap()
+                .unwrap()
+        };
+        assert_eq!(
+            serde_json::json!([1, 3]),
+            run(r#".jobs[?(.state == "failed")].id"#)
+        );
+        assert_eq!(
+            serde_json::json!([2]),
+            run(r#".jobs[?(.state != "failed")].id"#)
+        );
+        assert_eq!(serde_json::json!([1]), run(".jobs[?(.tries > 1)].id"));
+        assert_eq!(serde_json::json!([2, 3]), run(".jobs[?(.tries <= 1.0)].id"));
+        assert_eq!(serde_json::json!([3]), run(".jobs[?(.owner.name)].id"));
+        assert_eq!(serde_json::json!([2, 3]), run(".jobs[?(.id >= .tries)].id"));
+        assert_eq!(serde_json::json!([]), run(".jobs[?(.state < 3)]"));
+        assert_eq!(
+            serde_json::json!([{"name": "x"}]),
+            run(r#"..[?(.state == "failed")].owner"#)
+        );
+
+        let digits = vec![Point(1, 2), Point(3, 4)];
+        assert_eq!(
+            serde_json::json!([[3, 4]]),
+            JSONQuery::parse("[?(@[0] > 2)]")
+                .unwrap()
+                .execute(&digits)
+                .unwrap()
+                .unwrap()
+        );
+
+        // Filters test the values of maps and structs too, and keep looking after the first.
+        let scores = serde_json::json!({"a": {"x": 1, "y": 2, "z": 3}, "b": "text"});
+        let run = |q: &str| JSONQuery::parse(q).unwrap().execute(&scores).unwrap();
+        assert_eq!(Some(serde_json::json!([2, 3])), run(".a[?(@ > 1)]"));
+        assert_eq!(
+            Some(serde_json::json!(["text"])),
+            run(r#"[?(@ == "text")]"#)
+        );
+        assert_eq!(Some(serde_json::json!([1])), run("..[?(@.x)].x"));
+        let nested = NestedStructs {
+            dog: Example {
+                name: "Buddy".into(),
+                age: 14,
+                favorites: vec![],
+            },
+            truthiness: true,
+            score: -77,
+        };
+        assert_eq!(
+            Some(serde_json::json!([-77])),
+            JSONQuery::parse("[?(@ < 0)]")
+                .unwrap()
+                .execute(&nested)
+                .unwrap()
+        );
+        let pets = vec![Pet::Dog(nested.dog.clone()), Pet::Bird];
+        assert_eq!(
+            Some(serde_json::json!(["Buddy"])),
+            JSONQuery::parse("[*][?(@.age > 10)].name")
+                .unwrap()
+                .execute(&pets)
+                .unwrap()
+        );
+        // Tuple and struct variants are buffered, so filters can test their fields too.
+        let pets = vec![
+            Pet::Cat { lives: 9 },
+            Pet::Bird,
+            Pet::Dog(nested.dog.clone()),
+            Pet::Digits(1, 2, 3),
+            Pet::Cat { lives: 3 },
+        ];
+        let run = |q: &str| JSONQuery::parse(q).unwrap().execute(&pets).unwrap();
+        assert_eq!(
+            Some(serde_json::json!([{"lives": 9}, {"lives": 3}])),
+            run("[*][?(@.lives)]")
+        );
+        assert_eq!(
+            Some(serde_json::json!([9])),
+            run("[*][?(@.lives > 5)].lives")
+        );
+        assert_eq!(
+            Some(serde_json::json!([[1, 2, 3]])),
+            run("[*][?(@[0] == 1)]")
+        );
+        assert_eq!(Some(serde_json::json!([2])), run("[*][?(@[2] == 3)][1]"));
+        assert_eq!(
+            Some(serde_json::json!([9, 3, 9])),
+            run("[*].Cat.lives,[*][?(@.lives > 5)].lives")
+        );
+        let mut owners = HashMap::new();
+        owners.insert("sam", pets);
+        let run = |q: &str| JSONQuery::parse(q).unwrap().execute(&owners).unwrap();
+        assert_eq!(
+            Some(serde_json::json!([9])),
+            run("..[?(@.lives > 5)].lives")
+        );
+        assert_eq!(
+            Some(serde_json::json!(["Buddy"])),
+            run("..[?(@.age > 10)].name")
+        );
+        assert_eq!(Some(serde_json::json!([3])), run("..[?(@[1] == 2)][2]"));
+    }
+
+    #[test]
+    fn test_jsonpath() {
+        let data: JV = serde_json::from_str(
+            r#"{"store": {"book": [
+                {"title": "A", "price": 8.95, "tags": ["x"]},
+                {"title": "B", "price": 12.99},
+                {"title": "C", "price": 8.99, "isbn": "0-553"}
+            ]}}"#,
+        )
+        .unwrap();
+        let run = |q: &str| {
+            JSONQuery::parse_jsonpath(q)
                 .unwrap()
                 .execute(&data)
                 .unwrap()
                 .unwrap()
+        };
+        assert_eq!(serde_json::json!("B"), run("$.store.book[1].title"));
+        assert_eq!(
+            serde_json::json!("C"),
+            run("$['store']['book'][-1]['title']")
+        );
+        assert_eq!(
+            serde_json::json!(["A", "C"]),
+            run("$..book[?@.price < 10].title")
+        );
+        assert_eq!(serde_json::json!(["0-553"]), run("$..book[?@.isbn].isbn"));
+        // Filters select object members as well as array elements.
+        assert_eq!(serde_json::json!([12.99]), run("$.store.book[1][?@ > 10]"));
+        // JSONPath wildcards select the children of arrays and objects alike.
+        assert_eq!(
+            serde_json::json!(["A", "B", "C"]),
+            run("$.store.book.*.title")
+        );
+        assert_eq!(serde_json::json!(["x"]), run("$..tags[*]"));
+        assert_eq!(
+            serde_json::json!(["A", "C"]),
+            run("$.store.book[0, 2].title")
+        );
+        assert_eq!(
+            serde_json::json!(["A", 8.95, "B", 12.99, "C", 8.99]),
+            run("$.store.book[*]['title', 'price']")
         );
     }
-    
+
+    #[test]
+    fn test_unions() {
+        let data = NestedStructs {
+            dog: Example {
+                name: "Buddy".into(),
+                age: 14,
+                favorites: vec!["walks".into(), "naps".into()],
+            },
+            truthiness: false,
+            score: -77,
+        };
+        let run = |q: &str| {
+            JSONQuery::parse(q)
+                .unwrap()
+                .execute(&data)
+                .unwrap()
+                .unwrap()
+        };
+        assert_eq!(serde_json::json!(["Buddy", -77]), run(".dog.name,.score"));
+        assert_eq!(serde_json::json!([-77, "Buddy"]), run(".score, .dog.name"));
+        assert_eq!(
+            serde_json::json!([14, "walks", "naps"]),
+            run(".dog(.age,.favorites[*])")
+        );
+        assert_eq!(serde_json::json!([-77]), run(".missing,.score"));
+        // One value can be a match for both branches, even when one is inside the other.
+        assert_eq!(
+            serde_json::json!([["walks", "naps"], "naps", "naps"]),
+            run(".dog.favorites(@,[1],[-1])")
+        );
+    }
+
+    /// Counts how many times it gets serialized.
+    struct Counted<'a, T>(&'a T, &'a std::cell::Cell<usize>);
+
+    impl<T: serde::Serialize> serde::Serialize for Counted<'_, T> {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            self.1.set(self.1.get() + 1);
+            self.0.serialize(serializer)
+        }
+    }
+
+    #[test]
+    fn test_query_set() {
+        let data = NestedStructs {
+            dog: Example {
+                name: "Buddy".into(),
+                age: 14,
+                favorites: vec!["walks".into(), "naps".into()],
+            },
+            truthiness: false,
+            score: -77,
+        };
+        let queries = QuerySet::parse(&[
+            ".dog.name",
+            ".dog.favorites[1]",
+            ".dog.favorites[*]",
+            ".dog",
+            ".nope",
+            "{s: .score, n: .dog.name}",
+            ".dog.name",
+        ])
+        .unwrap();
+        let calls = std::cell::Cell::new(0);
+        let results = queries.execute(&Counted(&data, &calls)).unwrap();
+        assert_eq!(1, calls.get());
+        assert_eq!(queries.queries().len(), results.len());
+        for (query, result) in queries.queries().iter().zip(results) {
+            assert_eq!(query.execute(&data).unwrap(), result, "{}", query);
+        }
+        assert_eq!(
+            Vec::<Option<JV>>::new(),
+            QuerySet::default().execute(&data).unwrap()
+        );
+    }
+
+    /// The numbers below ``limit``; ``asked`` counts how many the serializer got through.
+    struct Numbers {
+        limit: usize,
+        asked: std::cell::Cell<usize>,
+    }
+
+    impl Numbers {
+        fn new(limit: usize) -> Self {
+            Self {
+                limit,
+                asked: std::cell::Cell::new(0),
+            }
+        }
+    }
+
+    impl serde::Serialize for Numbers {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            use serde::ser::SerializeSeq;
+            let mut seq = serializer.serialize_seq(Some(self.limit))?;
+            for i in 0..self.limit {
+                self.asked.set(i + 1);
+                seq.serialize_element(&i)?;
+            }
+            seq.end()
+        }
+    }
+
+    #[test]
+    fn test_early_termination() {
+        let run = |q: &str, data: &Numbers| JSONQuery::parse(q).unwrap().execute(data).unwrap();
+        let data = Numbers::new(1_000_000);
+        assert_eq!(Some(JV::from(2)), run("[2]", &data));
+        assert_eq!(3, data.asked.get());
+
+        let data = Numbers::new(1_000_000);
+        assert_eq!(Some(serde_json::json!([1, 2, 3])), run("[1:4]", &data));
+        assert_eq!(4, data.asked.get());
+
+        let data = Numbers::new(1_000_000);
+        assert_eq!(Some(serde_json::json!([7, 5])), run("[7],[5]", &data));
+        assert_eq!(8, data.asked.get());
+
+        // Wildcards and filters need to see everything.
+        let data = Numbers::new(100);
+        assert_eq!(Some(serde_json::json!([3])), run("[?(@ == 3)]", &data));
+        assert_eq!(100, data.asked.get());
+
+        // Each sequence stops once it is done, even though the other one still has matches.
+        let mut data: HashMap<&str, Numbers> = HashMap::default();
+        data.insert("a", Numbers::new(10));
+        data.insert("b", Numbers::new(10));
+        let found = JSONQuery::parse(".a[1],.b[2]")
+            .unwrap()
+            .execute(&data)
+            .unwrap();
+        assert_eq!(Some(serde_json::json!([1, 2])), found);
+        assert_eq!((2, 3), (data["a"].asked.get(), data["b"].asked.get()));
+    }
+
+    /// Always fails to serialize.
+    struct Broken;
+
+    impl serde::Serialize for Broken {
+        fn serialize<S: serde::Serializer>(&self, _serializer: S) -> Result<S::Ok, S::Error> {
+            Err(serde::ser::Error::custom("broken"))
+        }
+    }
+
+    #[test]
+    fn test_serialization_errors() {
+        let data = vec![(1, Broken)];
+        // We stop before we ever get to the broken part...
+        assert_eq!(
+            Some(JV::from(1)),
+            JSONQuery::parse("[0][0]").unwrap().execute(&data).unwrap()
+        );
+        // ...but errors are still errors.
+        assert_eq!(
+            Err(QueryExecErr::Serialization("broken".into())),
+            JSONQuery::parse("[0][1]").unwrap().execute(&data)
+        );
+        assert_eq!(
+            Err(QueryExecErr::Serialization("broken".into())),
+            JSONQuery::parse(".a").unwrap().execute(&Broken)
+        );
+
+        // Skipping ahead earlier in the same run must not hide the real error.
+        let data = Skips {
+            items: vec![Abc { a: 1, b: 2, c: 3 }; 2],
+            bad: vec![BrokenA { a: Broken }],
+        };
+        for query in &[".items[*].a,.bad[*].a", "..a", ".bad[0].a"] {
+            assert_eq!(
+                Err(QueryExecErr::Serialization("broken".into())),
+                JSONQuery::parse(query).unwrap().execute(&data),
+                "{}",
+                query
+            );
+        }
+    }
+
+    #[derive(Clone, Serialize)]
+    struct Abc {
+        a: u32,
+        b: u32,
+        c: u32,
+    }
+
+    #[derive(Serialize)]
+    struct BrokenA {
+        a: Broken,
+    }
+
+    #[derive(Serialize)]
+    struct Skips {
+        items: Vec<Abc>,
+        bad: Vec<BrokenA>,
+    }
+
+    /// Serializes the even numbers below ``limit`` without saying how many there are.
+    struct Evens {
+        limit: u32,
+    }
+
+    impl serde::Serialize for Evens {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.collect_seq((0..self.limit).filter(|x| x % 2 == 0))
+        }
+    }
+
+    /// Promises two elements but gives three.
+    struct Liar;
+
+    impl serde::Serialize for Liar {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            use serde::ser::SerializeSeq;
+            let mut seq = serializer.serialize_seq(Some(2))?;
+            for x in 0..3 {
+                seq.serialize_element(&x)?;
+            }
+            seq.end()
+        }
+    }
+
+    /// Gives a map value without a key.
+    struct Keyless;
+
+    impl serde::Serialize for Keyless {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            use serde::ser::SerializeMap;
+            let mut map = serializer.serialize_map(None)?;
+            map.serialize_value(&1)?;
+            map.end()
+        }
+    }
+
+    #[test]
+    fn test_unknown_lengths() {
+        let data = Evens { limit: 10 };
+        let run = |q: &str| JSONQuery::parse(q).unwrap().execute(&data).unwrap();
+        assert_eq!(Some(serde_json::json!([0, 2, 4, 6, 8])), run(""));
+        assert_eq!(Some(JV::from(4)), run("[2]"));
+        assert_eq!(None, run("[7]"));
+        assert_eq!(Some(serde_json::json!([2, 6])), run("[1:4:2]"));
+        // Without a length, we can't count from the end.
+        let unknown = |q: &str| JSONQuery::parse(q).unwrap().execute(&data).unwrap_err();
+        assert_eq!(
+            QueryExecErr::Unsupported(
+                "a negative index into a sequence of unknown length at ".into()
+            ),
+            unknown("[-1]")
+        );
+        assert!(matches!(unknown("[-3:]"), QueryExecErr::Unsupported(_)));
+        assert!(matches!(unknown("..[:-1]"), QueryExecErr::Unsupported(_)));
+        let nested = vec![Evens { limit: 4 }];
+        assert!(matches!(
+            JSONQuery::parse("[0][-1]").unwrap().execute(&nested),
+            Err(QueryExecErr::Unsupported(_))
+        ));
+        assert_eq!(
+            Some(JV::from(0)),
+            JSONQuery::parse("[-1][0]")
+                .unwrap()
+                .execute(&nested)
+                .unwrap()
+        );
+    }
+
+    #[test]
+    fn test_bad_serializers() {
+        assert_eq!(
+            Err(QueryExecErr::WrongLength {
+                expected: 2,
+                found: 3
+            }),
+            JSONQuery::parse("").unwrap().execute(&Liar)
+        );
+        assert!(matches!(
+            JSONQuery::parse(".a").unwrap().execute(&Keyless),
+            Err(QueryExecErr::InternalError(_))
+        ));
+    }
+
+    #[test]
+    fn test_execute_as() {
+        let data = NestedStructs {
+            dog: Example {
+                name: "Buddy".into(),
+                age: 14,
+                favorites: vec!["walks".into(), "naps".into()],
+            },
+            truthiness: false,
+            score: -77,
+        };
+        let query = |q: &str| JSONQuery::parse(q).unwrap();
+        assert_eq!(
+            Some("Buddy".to_string()),
+            query(".dog.name").execute_as(&data).unwrap()
+        );
+        assert_eq!(
+            Some(vec!["walks".to_string(), "naps".to_string()]),
+            query(".dog.favorites").execute_as(&data).unwrap()
+        );
+        assert_eq!(
+            Some((14u8, false)),
+            query(".dog.age,.truthiness").execute_as(&data).unwrap()
+        );
+        assert_eq!(None, query(".cat").execute_as::<String>(&data).unwrap());
+
+        match query(".score").execute_as::<u32>(&data) {
+            Err(QueryExecErr::Deserialization(failed, _)) => {
+                assert_eq!(query(".score"), failed)
+            }
+            other => panic!("expected a Deserialization error, got {:?}", other),
+        }
+    }
+
+    /// Serializes as bytes, like ``serde_bytes::ByteBuf`` does.
+    struct Blob(Vec<u8>);
+
+    impl serde::Serialize for Blob {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            serializer.serialize_bytes(&self.0)
+        }
+    }
+
+    #[derive(Serialize)]
+    struct Packet {
+        id: u32,
+        data: Blob,
+    }
+
+    #[test]
+    fn test_bytes() {
+        let packet = Packet {
+            id: 3,
+            data: Blob(vec![1, 2, 3, 255]),
+        };
+        let run = |q: &str, bytes: BytesFormat| {
+            JSONQuery::parse(q)
+                .unwrap()
+                .execute_with(&packet, &ExecOptions { bytes })
+                .unwrap()
+        };
+        assert_eq!(
+            Some(serde_json::json!([1, 2, 3, 255])),
+            run(".data", BytesFormat::Array)
+        );
+        assert_eq!(
+            Some(serde_json::json!({"id": 3, "data": [1, 2, 3, 255]})),
+            run("", BytesFormat::Array)
+        );
+        assert_eq!(
+            Some(serde_json::json!("AQID/w==")),
+            run(".data", BytesFormat::Base64)
+        );
+        assert_eq!(
+            Some(serde_json::json!({"id": 3, "data": "010203ff"})),
+            run("", BytesFormat::Hex)
+        );
+        for bytes in &[BytesFormat::Array, BytesFormat::Base64, BytesFormat::Hex] {
+            assert_eq!(Some(JV::from(255)), run(".data[-1]", *bytes));
+            assert_eq!(Some(serde_json::json!([2, 3])), run(".data[1:3]", *bytes));
+        }
+        assert_eq!(
+            Some(serde_json::json!(["AQID/w==", 1])),
+            run(".data,.data[0]", BytesFormat::Base64)
+        );
+
+        let base64 = |text: &str| {
+            JSONQuery::parse("")
+                .unwrap()
+                .execute_with(
+                    &Blob(text.as_bytes().to_vec()),
+                    &ExecOptions {
+                        bytes: BytesFormat::Base64,
+                    },
+                )
+                .unwrap()
+                .unwrap()
+        };
+        assert_eq!("", base64(""));
+        assert_eq!("Zg==", base64("f"));
+        assert_eq!("Zm8=", base64("fo"));
+        assert_eq!("Zm9v", base64("foo"));
+        assert_eq!("Zm9vYmFy", base64("foobar"));
+    }
+
+    #[derive(Serialize, PartialEq, Eq, PartialOrd, Ord)]
+    enum Color {
+        Red,
+        Green,
+    }
+
+    #[test]
+    fn test_non_string_keys() {
+        let mut sessions: HashMap<u64, &str> = HashMap::default();
+        sessions.insert(12345, "alice");
+        sessions.insert(7, "bob");
+        let mut data = HashMap::new();
+        data.insert("sessions", sessions);
+        let run = |q: &str, data: &dyn AnySerializable| {
+            JSONQuery::parse(q).unwrap().execute(data).unwrap()
+        };
+        assert_eq!(Some(JV::from("alice")), run(".sessions.12345", &data));
+        assert_eq!(None, run(".sessions.12", &data));
+        assert_eq!(Some(serde_json::to_value(&data).unwrap()), run("", &data));
+
+        let mut flags = std::collections::BTreeMap::new();
+        flags.insert(true, 1);
+        flags.insert(false, 0);
+        assert_eq!(Some(JV::from(1)), run(".true", &flags));
+
+        let mut letters = std::collections::BTreeMap::new();
+        letters.insert('x', -1i8);
+        assert_eq!(Some(JV::from(-1)), run(".x", &letters));
+
+        let mut colors = std::collections::BTreeMap::new();
+        colors.insert(Color::Red, "#f00");
+        colors.insert(Color::Green, "#0f0");
+        assert_eq!(Some(JV::from("#0f0")), run(".Green", &colors));
+        assert_eq!(
+            Some(serde_json::json!({"Red": "#f00", "Green": "#0f0"})),
+            run("", &colors)
+        );
+
+        let mut pairs = std::collections::BTreeMap::new();
+        pairs.insert((1, 2), "no");
+        assert!(matches!(
+            JSONQuery::parse(".x").unwrap().execute(&pairs),
+            Err(QueryExecErr::BadMapKey(_))
+        ));
+    }
+
+    #[test]
+    fn test_paths() {
+        let data = serde_json::json!({
+            "a": [{"x": 1}, {"y": 2}, {"x": {"x": 3}}],
+            "b": "hi",
+        });
+        let run = |q: &str| -> Vec<(String, JV)> {
+            JSONQuery::parse(q)
+                .unwrap()
+                .execute_with_paths(&data)
+                .unwrap()
+                .into_iter()
+                .map(|(path, value)| (path.to_string(), value))
+                .collect()
+        };
+        let found = |pairs: &[(&str, JV)]| -> Vec<(String, JV)> {
+            pairs
+                .iter()
+                .map(|(path, value)| (path.to_string(), value.clone()))
+                .collect()
+        };
+        assert_eq!(found(&[(".b", JV::from("hi"))]), run(".b"));
+        assert_eq!(found(&[]), run(".c"));
+        assert_eq!(
+            found(&[
+                (".a[0].x", JV::from(1)),
+                (".a[2].x", serde_json::json!({"x": 3}))
+            ]),
+            run(".a[*].x")
+        );
+        // Nested matches stay in document order.
+        assert_eq!(
+            found(&[
+                (".a[0].x", JV::from(1)),
+                (".a[2].x", serde_json::json!({"x": 3})),
+                (".a[2].x.x", JV::from(3)),
+            ]),
+            run("..x")
+        );
+        assert_eq!(
+            found(&[(".a[1]", serde_json::json!({"y": 2}))]),
+            run(".a[?(@.y == 2)]")
+        );
+        assert_eq!(found(&[(".b", JV::from("hi"))]), run(".b,.missing"));
+        // An object is found where we started building it.
+        assert_eq!(
+            found(&[(".a[1]", serde_json::json!({"y": 2, "x": null}))]),
+            run(".a[1]{y: .y, x: .x}")
+        );
+    }
+
+    #[test]
+    fn test_list_paths() {
+        let data = NestedStructs {
+            dog: Example {
+                name: "Buddy".into(),
+                age: 14,
+                favorites: vec!["walks".into(), "naps".into()],
+            },
+            truthiness: false,
+            score: -77,
+        };
+        let list = |data: &dyn AnySerializable, options: &ListOptions| -> Vec<String> {
+            list_paths(data, options)
+                .unwrap()
+                .into_iter()
+                .map(|(path, kind)| match kind {
+                    Some(kind) => format!("{} {}", path, kind),
+                    None => path.to_string(),
+                })
+                .collect()
+        };
+        assert_eq!(
+            vec![
+                ".dog",
+                ".dog.name",
+                ".dog.age",
+                ".dog.favorites",
+                ".dog.favorites[0]",
+                ".dog.favorites[1]",
+                ".truthiness",
+                ".score",
+            ],
+            list(&data, &ListOptions::default())
+        );
+        let options = ListOptions {
+            max_depth: Some(2),
+            collapse_indices: true,
+            types: true,
+        };
+        assert_eq!(
+            vec![
+                ".dog object",
+                ".dog.name string",
+                ".dog.age integer",
+                ".dog.favorites array",
+                ".truthiness boolean",
+                ".score integer",
+            ],
+            list(&data, &options)
+        );
+        let options = ListOptions {
+            collapse_indices: true,
+            types: true,
+            ..Default::default()
+        };
+        let data = serde_json::json!([{"x": 1}, {"x": 2.5}, {"x": null, "y": {}}, {"x": 3}]);
+        assert_eq!(
+            vec![
+                "[*] object",
+                "[*].x integer",
+                "[*].x number",
+                "[*].x null",
+                "[*].y object",
+            ],
+            list(&data, &options)
+        );
+
+        let mut data: HashMap<u32, Vec<Pet>> = HashMap::default();
+        data.insert(
+            7,
+            vec![Pet::Cat { lives: 9 }, Pet::Bird, Pet::Digits(1, 2, 3)],
+        );
+        assert_eq!(
+            vec![
+                ".7 array",
+                ".7[*] object",
+                ".7[*].Cat object",
+                ".7[*].Cat.lives integer",
+                ".7[*] string",
+                ".7[*].Digits array",
+                ".7[*].Digits[*] integer",
+            ],
+            list(&data, &options)
+        );
+        assert_eq!(Vec::<String>::new(), list(&5, &options));
+    }
+
+    #[derive(Serialize)]
+    struct Visit {
+        page: String,
+        #[serde(skip_serializing_if = "Option::is_none")]
+        referrer: Option<String>,
+        seconds: Option<f32>,
+        pet: Pet,
+    }
+
+    #[test]
+    fn test_infer_schema() {
+        let visit = Visit {
+            page: "/".into(),
+            referrer: None,
+            seconds: None,
+            pet: Pet::Bird,
+        };
+        assert_eq!(
+            serde_json::json!({
+                "$schema": "https://json-schema.org/draft/2020-12/schema",
+                "type": "object",
+                "properties": {
+                    "page": {"type": "string"},
+                    "seconds": {"type": "null"},
+                    "pet": {"type": "string"},
+                },
+                "required": ["page", "pet", "seconds"],
+            }),
+            infer_schema(&visit).unwrap()
+        );
+
+        let mut schema = Schema::infer(&visit).unwrap();
+        schema
+            .add(&Visit {
+                page: "/about".into(),
+                referrer: Some("/".into()),
+                seconds: Some(1.5),
+                pet: Pet::Digits(1, 2, 3),
+            })
+            .unwrap();
+        assert_eq!(
+            serde_json::json!({
+                "$schema": "https://json-schema.org/draft/2020-12/schema",
+                "type": "object",
+                "properties": {
+                    "page": {"type": "string"},
+                    "referrer": {"type": "string"},
+                    "seconds": {"type": ["null", "number"]},
+                    "pet": {
+                        "type": ["string", "object"],
+                        "properties": {
+                            "Digits": {"type": "array", "items": {"type": "integer"}},
+                        },
+                        "required": ["Digits"],
+                    },
+                },
+                "required": ["page", "pet", "seconds"],
+            }),
+            schema.to_json()
+        );
+        assert_eq!(
+            serde_json::json!({
+                "$schema": "https://json-schema.org/draft/2020-12/schema",
+                "type": "integer",
+            }),
+            infer_schema(&7).unwrap()
+        );
+    }
+
+    #[derive(Serialize)]
+    struct Account {
+        name: String,
+        token: String,
+        pets: Vec<Pet>,
+    }
+
+    /// Has one more session each time it's serialized.
+    struct Growing(std::cell::Cell<u32>);
+
+    impl serde::Serialize for Growing {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let count = self.0.replace(self.0.get() + 1);
+            let sessions: Vec<JV> = (0..count)
+                .map(|id| serde_json::json!({"id": id, "token": format!("secret{}", id)}))
+                .collect();
+            serde_json::json!({ "sessions": sessions }).serialize(serializer)
+        }
+    }
+
+    #[test]
+    fn test_redact() {
+        let data = Account {
+            name: "sam".into(),
+            token: "hunter2".into(),
+            pets: vec![
+                Pet::Cat { lives: 9 },
+                Pet::Bird,
+                Pet::Digits(4, 5, 6),
+                Pet::Cat { lives: 3 },
+            ],
+        };
+        let run = |deny: &[&str], redaction: &Redaction| {
+            let deny: Vec<JSONQuery> = deny.iter().map(|q| JSONQuery::parse(q).unwrap()).collect();
+            redact(&data, &deny, redaction).unwrap()
+        };
+        let hidden = Redaction::Replace(JV::Null);
+        assert_eq!(serde_json::to_value(&data).unwrap(), run(&[], &hidden));
+        assert_eq!(
+            serde_json::to_value(&data).unwrap(),
+            run(&[".missing"], &hidden)
+        );
+        assert_eq!(
+            serde_json::json!({
+                "name": "sam",
+                "token": null,
+                "pets": [{"Cat": {"lives": null}}, "Bird", {"Digits": [4, 5, 6]}, {"Cat": {"lives": null}}],
+            }),
+            run(&[".token", "..lives"], &hidden)
+        );
+        // Removing elements keeps the right ones, even when a removed element has removed parts.
+        assert_eq!(
+            serde_json::json!({
+                "name": "sam",
+                "pets": [{"Cat": {}}, {"Digits": [4]}],
+            }),
+            run(
+                &[
+                    ".token",
+                    ".pets[1]",
+                    ".pets[3]",
+                    "..lives",
+                    ".pets[2].Digits[1:]"
+                ],
+                &Redaction::Remove
+            )
+        );
+        assert_eq!(
+            serde_json::json!({"name": "sam", "pets": []}),
+            run(&[".token", ".pets[*]"], &Redaction::Remove)
+        );
+        assert_eq!(
+            serde_json::json!({"name": "[redacted]", "token": "[redacted]", "pets": "[redacted]"}),
+            run(&[".name,.token", ".pets"], &Redaction::default())
+        );
+        assert_eq!(
+            JV::from("gone"),
+            run(&[".pets[0]", "@"], &Redaction::Replace("gone".into()))
+        );
+
+        // A root that changes every time it's serialized still has every secret hidden.
+        let live = Growing(std::cell::Cell::new(1));
+        let deny = vec![JSONQuery::parse(".sessions[*].token").unwrap()];
+        assert_eq!(
+            serde_json::json!({"sessions": [{"id": 0, "token": "[redacted]"}]}),
+            redact(&live, &deny, &Redaction::default()).unwrap()
+        );
+        assert_eq!(JV::Null, run(&["@"], &Redaction::Remove));
+    }
+
+    #[test]
+    fn test_policy() {
+        let data = Account {
+            name: "sam".into(),
+            token: "hunter2".into(),
+            pets: vec![Pet::Cat { lives: 9 }, Pet::Bird, Pet::Digits(1, 2, 3)],
+        };
+        let policy = QueryPolicy::parse(&[".name", ".pets[*].Cat"]).unwrap();
+        let run = |q: &str| policy.execute(&JSONQuery::parse(q).unwrap(), &data);
+        let forbidden = |q: &str| Err(QueryExecErr::Forbidden(JSONQuery::parse(q).unwrap()));
+
+        assert_eq!(Ok(Some(JV::from("sam"))), run(".name"));
+        assert_eq!(Ok(Some(JV::from(9))), run(".pets[0].Cat.lives"));
+        assert_eq!(Ok(Some(serde_json::json!([9]))), run(".pets[*].Cat.lives"));
+        assert_eq!(Ok(None), run(".pets[5].Cat"));
+        assert_eq!(forbidden(".token"), run(".token"));
+        assert_eq!(forbidden(".pets[*]"), run(".pets[*]"));
+        // Wildcards are checked against what they actually find...
+        assert_eq!(Ok(Some(serde_json::json!([9]))), run("..lives"));
+        assert_eq!(forbidden(".token"), run(".*"));
+        assert_eq!(forbidden(".pets[2].Digits"), run(".pets[*].*"));
+        // ...but filters must only test allowed paths.
+        assert_eq!(forbidden(".pets[*].lives"), run(".pets[?(@.lives)]"));
+        assert_eq!(forbidden(".token"), run("{name: .name, token: .token}"));
+
+        let policy = QueryPolicy::default();
+        assert_eq!(
+            Err(QueryExecErr::Forbidden(JSONQuery::parse("@").unwrap())),
+            policy.check(&JSONQuery::parse("@").unwrap())
+        );
+    }
+
+    #[test]
+    fn test_builder() {
+        let data = serde_json::json!({"a.b": [{"first name": "sam"}, {"\"": 1}]});
+        let query = JSONQuery::root().field("a.b").index(0).field("first name");
+        assert_eq!(Some(JV::from("sam")), query.execute(&data).unwrap());
+        // Display quotes the awkward keys, so it parses back to the same query.
+        assert_eq!(query, JSONQuery::parse(&query.to_string()).unwrap());
+
+        let mut query = query.parent().unwrap();
+        query.push(QueryElement::any_field());
+        assert_eq!(
+            Some(serde_json::json!(["sam"])),
+            query.execute(&data).unwrap()
+        );
+        assert_eq!(Some(QueryElement::any_field()), query.pop());
+        let quote = query.parent().unwrap().index(1).field("\"");
+        assert_eq!(Some(JV::from(1)), quote.execute(&data).unwrap());
+
+        let rest = JSONQuery::root().index(1);
+        let items = JSONQuery::root().field("a.b");
+        assert_eq!(items.clone().index(1), items.join(&rest));
+        assert_eq!(items, items.join(&JSONQuery::root()));
+        assert_eq!(
+            Some(data.clone()),
+            JSONQuery::root().execute(&data).unwrap()
+        );
+        assert_eq!(None, JSONQuery::root().parent());
+        assert_eq!(None, JSONQuery::root().pop());
+    }
+
+    #[test]
+    fn test_objects() {
+        let dogs = vec![
+            Example {
+                name: "Buddy".into(),
+                age: 14,
+                favorites: vec!["walks".into(), "naps".into()],
+            },
+            Example {
+                name: "Rex".into(),
+                age: 3,
+                favorites: vec![],
+            },
+        ];
+        let run = |q: &str| JSONQuery::parse(q).unwrap().execute(&dogs).unwrap();
+        assert_eq!(
+            Some(serde_json::json!({"name": "Buddy", "first": "walks", "years": 14})),
+            run("[0]{name: .name, first: .favorites[0], years: .age}")
+        );
+        assert_eq!(
+            Some(serde_json::json!([
+                {"name": "Buddy", "first": "walks", "all": ["walks", "naps"]},
+                {"name": "Rex", "first": null, "all": []}
+            ])),
+            run("[*]{name: .name, first: .favorites[0], all: .favorites[*]}")
+        );
+        assert_eq!(
+            Some(serde_json::json!({"first": {"name": "Buddy"}, "ages": [14, 3]})),
+            run("{first: [0]{name: .name}, ages: [*].age}")
+        );
+        assert_eq!(None, run("[2]{name: .name}"));
+    }
+
+    // tuple-struct
+    #[derive(Serialize)]
+    struct Point(u32, u32);
+
+    #[test]
+    fn test_tuple_struct() {
+        let data = vec![Point(1, 2), Point(3, 4)];
+        assert_eq!(
+            serde_json::to_value(vec![1, 2]).unwrap(),
+            JSONQuery::parse("[0]")
+                .unwrap()
+                .execute(&data)
+                .unwrap()
+                .unwrap()
+        );
+        assert_eq!(
+            4,
+            JSONQuery::parse("[1][1]")
+                .unwrap()
+                .execute(&data)
+                .unwrap()
+                .unwrap()
+        );
+    }
+
     #[test]
     fn test_array_as_root() {
         let data = &[Point(1, 2), Point(3, 4)];
@@ -390,7 +1690,7 @@ mod tests {
         ];
 
         assert_eq!(
-            serde_json::to_value(&vec![vec![1, 2, 3], vec![4, 5, 6]]).unwrap(),
+            serde_json::to_value(vec![vec![1, 2, 3], vec![4, 5, 6]]).unwrap(),
             JSONQuery::parse("[0]")
                 .unwrap()
                 .execute(&data)
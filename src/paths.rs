@@ -0,0 +1,138 @@
+use crate::query::{JSONQuery, QueryElement};
+use crate::query_executor::{ExecOptions, QueryExecErr, QueryExecutor};
+use crate::AnySerializable;
+use serde_json::Value as JSON;
+use std::collections::HashSet;
+
+/// The type of a JSON value, with the names JSON Schema uses for them.
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Debug, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum JSONType {
+    Null,
+    Boolean,
+    /// A number without a fractional part.
+    Integer,
+    Number,
+    String,
+    Array,
+    Object,
+}
+
+impl JSONType {
+    pub fn of(value: &JSON) -> Self {
+        match value {
+            JSON::Null => JSONType::Null,
+            JSON::Bool(_) => JSONType::Boolean,
+            JSON::Number(n) if n.is_f64() => JSONType::Number,
+            JSON::Number(_) => JSONType::Integer,
+            JSON::String(_) => JSONType::String,
+            JSON::Array(_) => JSONType::Array,
+            JSON::Object(_) => JSONType::Object,
+        }
+    }
+    /// The name JSON Schema gives this type.
+    pub fn name(&self) -> &'static str {
+        match self {
+            JSONType::Null => "null",
+            JSONType::Boolean => "boolean",
+            JSONType::Integer => "integer",
+            JSONType::Number => "number",
+            JSONType::String => "string",
+            JSONType::Array => "array",
+            JSONType::Object => "object",
+        }
+    }
+}
+
+impl std::fmt::Display for JSONType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+/// Settings for ``list_paths``; by default every path is listed once, without types.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub struct ListOptions {
+    /// Only list paths with at most this many fields and indices.
+    pub max_depth: Option<usize>,
+    /// Write every array index as ``[*]``, so the elements of an array share their paths.
+    pub collapse_indices: bool,
+    /// Also give the type of the value found at each path.
+    pub types: bool,
+}
+
+/// Every path that a query could address in any serde-serializable object, in document order.
+///
+/// The object itself (the empty path) is not listed. With ``types``, each path comes with the type of its value;
+/// if ``collapse_indices`` puts values of different types at one path, that path is listed once per type.
+///
+/// ```
+/// use access_json::{list_paths, JSONQuery, ListOptions};
+/// use serde_json::json;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let data = json!({"name": "Sam", "langs": ["rust", "ocaml"]});
+/// let options = ListOptions { collapse_indices: true, ..Default::default() };
+///
+/// let paths: Vec<String> = list_paths(&data, &options)?
+///     .into_iter()
+///     .map(|(path, _)| path.to_string())
+///     .collect();
+/// assert_eq!(vec![".langs", ".langs[*]", ".name"], paths);
+/// # Ok(())
+/// # }
+/// ```
+pub fn list_paths(
+    target: &dyn AnySerializable,
+    options: &ListOptions,
+) -> Result<Vec<(JSONQuery, Option<JSONType>)>, QueryExecErr> {
+    let mut runner = QueryExecutor::new(&[], &ExecOptions::default())?;
+    runner.list_paths(options.max_depth);
+    runner.run(target)?;
+
+    let mut listed = Vec::new();
+    let mut seen = HashSet::new();
+    for (mut path, kind) in runner.get_listing().into_iter().skip(1) {
+        if options.collapse_indices {
+            for elem in path.elements.iter_mut() {
+                if let QueryElement::ArrayItem(_) = elem {
+                    *elem = QueryElement::AnyItem;
+                }
+            }
+        }
+        let entry = (path, if options.types { kind } else { None });
+        if seen.insert(entry.clone()) {
+            listed.push(entry);
+        }
+    }
+    Ok(listed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_types() {
+        let data = serde_json::json!([null, true, 3, -3, 3.5, "x", [], {}]);
+        let types: Vec<JSONType> = data.as_array().unwrap().iter().map(JSONType::of).collect();
+        assert_eq!(
+            vec![
+                JSONType::Null,
+                JSONType::Boolean,
+                JSONType::Integer,
+                JSONType::Integer,
+                JSONType::Number,
+                JSONType::String,
+                JSONType::Array,
+                JSONType::Object,
+            ],
+            types
+        );
+        assert_eq!("boolean", JSONType::Boolean.to_string());
+        assert_eq!(
+            serde_json::json!("integer"),
+            serde_json::to_value(JSONType::Integer).unwrap()
+        );
+    }
+}
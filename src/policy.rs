@@ -0,0 +1,306 @@
+use crate::query::{JSONQuery, QueryElement};
+use crate::query_executor::{ExecOptions, QueryExecErr, QueryExecutor};
+use crate::query_parser::QueryParseErr;
+use crate::AnySerializable;
+use serde_json::Value as JSON;
+
+/// The only paths that queries may read, e.g., ``.status.*`` and ``.metrics``; everything under an allowed path is allowed too.
+///
+/// Queries are checked before they run. If a wildcard, slice or ``..`` might reach outside the policy, each path it actually found is checked as well.
+/// Filters are only allowed if every path they test is allowed, since we can't tell what they read after the fact.
+///
+/// ```
+/// use access_json::{JSONQuery, QueryExecErr, QueryPolicy};
+/// use serde_json::json;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let data = json!({"status": {"up": true, "load": 3}, "secret": "hunter2"});
+/// let policy = QueryPolicy::parse(&[".status.*"])?;
+///
+/// assert_eq!(Some(json!(3)), policy.execute(&JSONQuery::parse(".status.load")?, &data)?);
+/// assert_eq!(
+///     Err(QueryExecErr::Forbidden(JSONQuery::parse(".secret")?)),
+///     policy.check(&JSONQuery::parse(".secret")?)
+/// );
+/// assert_eq!(
+///     Err(QueryExecErr::Forbidden(JSONQuery::parse(".secret")?)),
+///     policy.execute(&JSONQuery::parse(".*.*,.secret")?, &data)
+/// );
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub struct QueryPolicy {
+    allowed: Vec<JSONQuery>,
+}
+
+/// What we can tell about a path before running it.
+#[derive(PartialEq, Eq, Debug)]
+enum Verdict {
+    Allowed,
+    /// Only the paths it finds can tell us.
+    Unknown,
+    Forbidden,
+}
+
+impl QueryPolicy {
+    pub fn new(allowed: Vec<JSONQuery>) -> Self {
+        Self { allowed }
+    }
+
+    /// Parse every allowed path with ``JSONQuery::parse``, stopping at the first error.
+    pub fn parse(allowed: &[&str]) -> Result<Self, QueryParseErr> {
+        let allowed = allowed
+            .iter()
+            .map(|input| JSONQuery::parse(input))
+            .collect::<Result<_, _>>()?;
+        Ok(Self::new(allowed))
+    }
+
+    pub fn allow(&mut self, path: JSONQuery) {
+        self.allowed.push(path);
+    }
+
+    pub fn allowed(&self) -> &[JSONQuery] {
+        &self.allowed
+    }
+
+    /// Refuse a query if it can only read paths outside the policy; ``execute`` may still refuse it later.
+    pub fn check(&self, query: &JSONQuery) -> Result<(), QueryExecErr> {
+        self.precheck(query).map(|_| ())
+    }
+
+    /// Execute a query against any serde-serializable object, if the policy allows everything it finds.
+    pub fn execute(
+        &self,
+        query: &JSONQuery,
+        target: &dyn AnySerializable,
+    ) -> Result<Option<JSON>, QueryExecErr> {
+        self.execute_with(query, target, &ExecOptions::default())
+    }
+
+    /// Execute a query with non-default settings; see ``JSONQuery::execute_with``.
+    pub fn execute_with(
+        &self,
+        query: &JSONQuery,
+        target: &dyn AnySerializable,
+        options: &ExecOptions,
+    ) -> Result<Option<JSON>, QueryExecErr> {
+        if !self.precheck(query)? {
+            return query.execute_with(target, options);
+        }
+        let mut runner = QueryExecutor::new(std::slice::from_ref(query), options)?;
+        runner.run(target)?;
+        let found = runner.get_paths().pop().unwrap_or_default();
+        if let Some((path, _)) = found.iter().find(|(path, _)| !self.covers(&path.elements)) {
+            return Err(QueryExecErr::Forbidden(path.clone()));
+        }
+        let mut values = found.into_iter().map(|(_, value)| value);
+        Ok(if query.is_singular() {
+            values.next()
+        } else {
+            Some(JSON::Array(values.collect()))
+        })
+    }
+
+    /// Returns whether the paths the query finds still need to be checked.
+    fn precheck(&self, query: &JSONQuery) -> Result<bool, QueryExecErr> {
+        let mut reads = Vec::new();
+        read_paths(Vec::new(), &query.elements, false, &mut reads);
+        let mut recheck = false;
+        for (path, tested) in reads {
+            match self.verdict(&path) {
+                Verdict::Allowed => {}
+                Verdict::Unknown if !tested => recheck = true,
+                _ => return Err(QueryExecErr::Forbidden(JSONQuery::new(path))),
+            }
+        }
+        Ok(recheck)
+    }
+
+    fn verdict(&self, path: &[QueryElement]) -> Verdict {
+        if self.covers(path) {
+            Verdict::Allowed
+        } else if path.iter().all(is_concrete) {
+            Verdict::Forbidden
+        } else if self
+            .allowed
+            .iter()
+            .any(|allowed| overlaps(&allowed.elements, path))
+        {
+            Verdict::Unknown
+        } else {
+            Verdict::Forbidden
+        }
+    }
+
+    /// Is everything this path can find inside some allowed path?
+    fn covers(&self, path: &[QueryElement]) -> bool {
+        self.allowed.iter().any(|allowed| {
+            allowed.elements.len() <= path.len()
+                && allowed
+                    .elements
+                    .iter()
+                    .zip(path)
+                    .all(|(allowed, elem)| covers(allowed, elem))
+        })
+    }
+}
+
+/// Each path a query reads, with Unions and Objects split into their branches.
+/// Filters also read their relative paths from each element; those are marked, since only they can't be checked after the fact.
+fn read_paths(
+    mut prefix: Vec<QueryElement>,
+    elements: &[QueryElement],
+    tested: bool,
+    reads: &mut Vec<(Vec<QueryElement>, bool)>,
+) {
+    for (i, elem) in elements.iter().enumerate() {
+        let rest = &elements[i + 1..];
+        match elem {
+            QueryElement::Union(branches) => {
+                for branch in branches {
+                    let path = [&branch.elements[..], rest].concat();
+                    read_paths(prefix.clone(), &path, tested, reads);
+                }
+                return;
+            }
+            QueryElement::Object(entries) => {
+                for (_, entry) in entries {
+                    let path = [&entry.elements[..], rest].concat();
+                    read_paths(prefix.clone(), &path, tested, reads);
+                }
+                return;
+            }
+            QueryElement::Filter(predicate) => {
+                for path in predicate.paths() {
+                    let mut element = prefix.clone();
+                    element.push(QueryElement::AnyItem);
+                    read_paths(element, &path.elements, true, reads);
+                }
+            }
+            _ => {}
+        }
+        prefix.push(elem.clone());
+    }
+    reads.push((prefix, tested));
+}
+
+/// Fields and indices name exactly one child.
+fn is_concrete(elem: &QueryElement) -> bool {
+    matches!(elem, QueryElement::Field(_) | QueryElement::ArrayItem(_))
+}
+
+/// Does an allowed element accept every child that a query element could find?
+fn covers(allowed: &QueryElement, elem: &QueryElement) -> bool {
+    match (allowed, elem) {
+        (_, QueryElement::RecursiveDescent) => false,
+        // ``.*`` and ``[*]`` both accept fields and indices.
+        (QueryElement::AnyField | QueryElement::AnyItem, _) => true,
+        // A JSON Pointer's ``/0`` allows index 0 and the key "0".
+        (QueryElement::IndexOrKey(_), _) => allowed == elem || allowed.matches(elem, None),
+        _ => allowed == elem,
+    }
+}
+
+/// Could a query path find something inside an allowed path?
+fn overlaps(allowed: &[QueryElement], path: &[QueryElement]) -> bool {
+    for (allowed, elem) in allowed.iter().zip(path) {
+        let disjoint = match (allowed, elem) {
+            (_, QueryElement::RecursiveDescent) | (QueryElement::RecursiveDescent, _) => {
+                return true
+            }
+            (QueryElement::Field(a), QueryElement::Field(b)) => a != b,
+            (QueryElement::ArrayItem(a), QueryElement::ArrayItem(b)) => a != b,
+            (QueryElement::IndexOrKey(x), other) | (other, QueryElement::IndexOrKey(x)) => {
+                match other {
+                    QueryElement::ArrayItem(y) | QueryElement::IndexOrKey(y) => x != y,
+                    QueryElement::Field(name) => *name != x.to_string(),
+                    _ => false,
+                }
+            }
+            (QueryElement::Field(_), elem) => {
+                !matches!(elem, QueryElement::AnyField | QueryElement::AnyItem)
+            }
+            (_, QueryElement::Field(_)) => {
+                !matches!(allowed, QueryElement::AnyField | QueryElement::AnyItem)
+            }
+            _ => false,
+        };
+        if disjoint {
+            return false;
+        }
+    }
+    // A shorter path only finds ancestors of the allowed path, not anything inside it.
+    path.len() >= allowed.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_verdicts() {
+        let policy = QueryPolicy::parse(&[".status.*", ".metrics", ".list[*].name"]).unwrap();
+        let verdict = |q: &str| {
+            let mut reads = Vec::new();
+            read_paths(
+                Vec::new(),
+                &JSONQuery::parse(q).unwrap().elements,
+                false,
+                &mut reads,
+            );
+            reads
+                .iter()
+                .map(|(path, _)| policy.verdict(path))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(vec![Verdict::Allowed], verdict(".metrics"));
+        assert_eq!(vec![Verdict::Allowed], verdict(".metrics..x"));
+        assert_eq!(vec![Verdict::Allowed], verdict(".status.up"));
+        assert_eq!(vec![Verdict::Allowed], verdict(".list[2:].name"));
+        assert_eq!(vec![Verdict::Forbidden], verdict(".status"));
+        assert_eq!(vec![Verdict::Forbidden], verdict(".secret"));
+        assert_eq!(vec![Verdict::Forbidden], verdict(".secret.*"));
+        // ``.*`` could find .metrics, and ``.list.*`` only finds what's around .list[*].name
+        assert_eq!(vec![Verdict::Unknown], verdict(".*"));
+        assert_eq!(vec![Verdict::Unknown], verdict(".*.*"));
+        assert_eq!(vec![Verdict::Unknown], verdict("..name"));
+        assert_eq!(vec![Verdict::Forbidden], verdict(".list.*"));
+        assert_eq!(vec![Verdict::Unknown], verdict(".list.*.*"));
+        assert_eq!(
+            vec![Verdict::Allowed, Verdict::Forbidden],
+            verdict(".metrics,.secret")
+        );
+        // The filter reads .list[*].id, as well as finding .list[*].name
+        assert_eq!(
+            vec![Verdict::Forbidden, Verdict::Allowed],
+            verdict(".list[?(@.id == 3)].name")
+        );
+
+        // ``[0]`` and ``."0"`` are different children; a JSON Pointer's ``/0`` could be either.
+        let policy = QueryPolicy::parse(&[".ids[0]", r#".keys."1""#]).unwrap();
+        let pointer = |p: &str| JSONQuery::from_json_pointer(p).unwrap();
+        assert!(!policy.covers(&JSONQuery::parse(r#".ids."0""#).unwrap().elements));
+        assert!(!policy.covers(&JSONQuery::parse(".keys[1]").unwrap().elements));
+        assert_eq!(
+            Verdict::Unknown,
+            policy.verdict(&pointer("/ids/0").elements)
+        );
+        assert_eq!(
+            Verdict::Forbidden,
+            policy.verdict(&pointer("/ids/1").elements)
+        );
+        let ids = serde_json::json!({"ids": [5], "keys": {"0": 6}});
+        let keys = serde_json::json!({"ids": {"0": 5}, "keys": [6, 7]});
+        assert_eq!(Ok(Some(5.into())), policy.execute(&pointer("/ids/0"), &ids));
+        assert!(policy.execute(&pointer("/ids/0"), &keys).is_err());
+        assert_eq!(Ok(None), policy.execute(&pointer("/keys/1"), &ids));
+        assert!(policy.execute(&pointer("/keys/1"), &keys).is_err());
+
+        let policy = QueryPolicy::new(vec![pointer("/ids/0")]);
+        assert!(policy.covers(&JSONQuery::parse(".ids[0]").unwrap().elements));
+        assert!(policy.covers(&JSONQuery::parse(r#".ids."0".x"#).unwrap().elements));
+        assert!(!policy.covers(&JSONQuery::parse(".ids[1]").unwrap().elements));
+    }
+}
@@ -0,0 +1,180 @@
+//! Python bindings for running queries against a serializable root, built with the ``pyo3`` feature.
+//!
+//! A Rust host wraps its root with ``QueryRoot::new`` and hands it to Python, where ``root.query(".items[0].name")``
+//! gives back plain Python values: dicts, lists, strings, numbers, booleans, or None when nothing matches.
+//! Bad queries raise ``QueryParseError``, and failed runs raise ``QueryExecError``; both are ``QueryError``s.
+use crate::query::JSONQuery;
+use crate::query_executor::QueryExecErr;
+use crate::query_parser::QueryParseErr;
+use crate::AnySerializable;
+use pyo3::create_exception;
+use pyo3::exceptions::PyException;
+use pyo3::prelude::*;
+use pyo3::types::{PyDict, PyList};
+use pyo3::IntoPyObjectExt;
+use serde_json::Value as JSON;
+
+create_exception!(
+    access_json,
+    QueryError,
+    PyException,
+    "A query could not be parsed or run."
+);
+create_exception!(
+    access_json,
+    QueryParseError,
+    QueryError,
+    "From ``QueryParseErr``."
+);
+create_exception!(
+    access_json,
+    QueryExecError,
+    QueryError,
+    "From ``QueryExecErr``."
+);
+
+impl From<QueryParseErr> for PyErr {
+    fn from(err: QueryParseErr) -> Self {
+        QueryParseError::new_err(err.to_string())
+    }
+}
+
+impl From<QueryExecErr> for PyErr {
+    fn from(err: QueryExecErr) -> Self {
+        QueryExecError::new_err(err.to_string())
+    }
+}
+
+/// A read-only root that Python code can query.
+#[pyclass(module = "access_json", frozen)]
+pub struct QueryRoot {
+    root: Box<dyn AnySerializable + Send + Sync>,
+}
+
+impl QueryRoot {
+    /// Wrap a root for Python; pass it along with ``Py::new``, or return it from a ``#[pyfunction]``.
+    ///
+    /// The root is serialized again for every query, so a type whose ``Serialize`` reads shared state gives live answers.
+    pub fn new<T>(root: T) -> Self
+    where
+        T: serde::Serialize + Send + Sync + 'static,
+    {
+        Self {
+            root: Box::new(root),
+        }
+    }
+}
+
+#[pymethods]
+impl QueryRoot {
+    /// Make a root from JSON text, e.g., from Python code that has no Rust root of its own.
+    #[staticmethod]
+    fn from_json(json: &str) -> PyResult<Self> {
+        let root: JSON = serde_json::from_str(json).map_err(QueryExecErr::from)?;
+        Ok(Self::new(root))
+    }
+
+    /// Run a query, and convert what it found to Python; None if nothing matched.
+    fn query(&self, py: Python<'_>, query: &str) -> PyResult<PyObject> {
+        let query = JSONQuery::parse(query)?;
+        let found = py.allow_threads(|| query.execute(self.root.as_ref()))?;
+        match found {
+            Some(found) => to_python(py, &found),
+            None => Ok(py.None()),
+        }
+    }
+}
+
+/// Convert a result to the Python value ``json.loads`` would give.
+fn to_python(py: Python<'_>, value: &JSON) -> PyResult<PyObject> {
+    Ok(match value {
+        JSON::Null => py.None(),
+        JSON::Bool(x) => x.into_py_any(py)?,
+        JSON::Number(x) => match (x.as_i64(), x.as_u64()) {
+            (Some(x), _) => x.into_py_any(py)?,
+            (_, Some(x)) => x.into_py_any(py)?,
+            _ => x.as_f64().unwrap_or(f64::NAN).into_py_any(py)?,
+        },
+        JSON::String(x) => x.into_py_any(py)?,
+        JSON::Array(items) => {
+            let list = PyList::empty(py);
+            for item in items {
+                list.append(to_python(py, item)?)?;
+            }
+            list.into_any().unbind()
+        }
+        JSON::Object(fields) => {
+            let dict = PyDict::new(py);
+            for (name, field) in fields {
+                dict.set_item(name, to_python(py, field)?)?;
+            }
+            dict.into_any().unbind()
+        }
+    })
+}
+
+/// Add ``QueryRoot`` and the exceptions to a module, e.g., one that a Rust host embeds in its interpreter.
+pub fn register(module: &Bound<'_, PyModule>) -> PyResult<()> {
+    let py = module.py();
+    module.add_class::<QueryRoot>()?;
+    module.add("QueryError", py.get_type::<QueryError>())?;
+    module.add("QueryParseError", py.get_type::<QueryParseError>())?;
+    module.add("QueryExecError", py.get_type::<QueryExecError>())?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pyo3::types::IntoPyDict;
+    use std::collections::BTreeMap;
+    use std::sync::{Arc, Mutex};
+
+    /// Serializes whatever is in the shared list right now.
+    struct Live(Arc<Mutex<Vec<String>>>);
+
+    impl serde::Serialize for Live {
+        fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            let mut root = BTreeMap::new();
+            root.insert("items", self.0.lock().unwrap().clone());
+            root.serialize(serializer)
+        }
+    }
+
+    #[test]
+    fn test_python() {
+        pyo3::prepare_freethreaded_python();
+        let items = Arc::new(Mutex::new(vec!["a".to_string()]));
+        Python::with_gil(|py| {
+            let module = PyModule::new(py, "access_json").unwrap();
+            register(&module).unwrap();
+            let root = Py::new(py, QueryRoot::new(Live(items.clone()))).unwrap();
+            let locals = [
+                ("access_json", module.into_any()),
+                ("root", root.into_bound(py).into_any()),
+            ]
+            .into_py_dict(py)
+            .unwrap();
+            let check = |code: &str| {
+                py.run(&std::ffi::CString::new(code).unwrap(), None, Some(&locals))
+                    .map_err(|err| err.print(py))
+                    .unwrap()
+            };
+
+            check("assert root.query('.items') == ['a']");
+            items.lock().unwrap().push("b".into());
+            check("assert root.query('.items[-1]') == 'b'");
+            check("assert root.query('.nope') is None");
+            check("assert root.query('.items[*]') == ['a', 'b']");
+            check(
+                "try:\n    root.query('.items[')\n    assert False\nexcept access_json.QueryParseError as err:\n    assert isinstance(err, access_json.QueryError)",
+            );
+            check(
+                "doc = access_json.QueryRoot.from_json('{\"a\": [1, 2.5, null, true, {\"b\": \"x\"}]}')\nassert doc.query('.a') == [1, 2.5, None, True, {'b': 'x'}]",
+            );
+            check(
+                "try:\n    access_json.QueryRoot.from_json('{')\n    assert False\nexcept access_json.QueryExecError:\n    pass",
+            );
+        });
+    }
+}
@@ -1,12 +1,46 @@
-use crate::query_executor::{QueryExecErr, QueryExecutor};
-use crate::query_parser::{parse_query, QueryParseErr};
+use crate::filter::Predicate;
+use crate::jsonpath_parser::parse_jsonpath;
+use crate::query_executor::{ExecOptions, QueryExecErr, QueryExecutor};
+use crate::query_parser::{parse_json_pointer, parse_query, QueryParseErr};
 use crate::AnySerializable;
-use serde::Serialize;
+use serde::de::DeserializeOwned;
 
 #[derive(Clone, PartialEq, Eq, Debug, Hash, Serialize, Deserialize)]
 pub enum QueryElement {
     Field(String),
     ArrayItem(usize),
+    /// A JSON Pointer token like ``/0``: the element at that index of a sequence, or the object key spelled the same way.
+    ///
+    /// Only ``from_json_pointer`` makes these; it prints as ``[0]``, which parses back as an ``ArrayItem``.
+    IndexOrKey(usize),
+    /// Counts back from the end of a sequence: ``[-1]`` is ``ArrayItemFromEnd(1)``, the last element. The offset is never 0.
+    ///
+    /// A sequence that does not give its length up front can't be counted back from; that is ``QueryExecErr::Unsupported``.
+    ArrayItemFromEnd(usize),
+    /// Matches every child: each field of a map or struct, or each element of a sequence: ``.*``
+    AnyField,
+    /// The same wildcard as AnyField, spelled with brackets like JSONPath allows: ``[*]``
+    AnyItem,
+    /// Matches any number of path elements, so that the next element is found at any depth: ``..``
+    RecursiveDescent,
+    /// Matches sequence elements with Python slice semantics: ``[start:end:step]``
+    ///
+    /// Negative bounds count back from the end of the sequence.
+    Slice {
+        start: Option<isize>,
+        end: Option<isize>,
+        step: usize,
+    },
+    /// Matches sequence elements, or the values in a map or struct, that pass a test: ``[?(.state == "failed")]``
+    Filter(Predicate),
+    /// Continues with each relative path and gives the matches of all of them, in order: ``(.name,.id)``
+    ///
+    /// At the top of a query the parentheses are optional: ``.name,.id``
+    Union(Vec<JSONQuery>),
+    /// Builds a new object, with each key holding the result of its relative path: ``{name: .a, age: .b}``
+    ///
+    /// Elements after it in a query continue each relative path, since the object is built from the data we visit.
+    Object(Vec<(String, JSONQuery)>),
 }
 
 impl QueryElement {
@@ -16,20 +50,231 @@ impl QueryElement {
     pub fn array_item(index: usize) -> Self {
         Self::ArrayItem(index)
     }
+    /// ``[-offset]``; an offset of 0 gives ``[0]``, just as the parser reads ``[-0]``.
+    pub fn index_or_key(index: usize) -> Self {
+        Self::IndexOrKey(index)
+    }
+    pub fn array_item_from_end(offset: usize) -> Self {
+        if offset == 0 {
+            Self::ArrayItem(0)
+        } else {
+            Self::ArrayItemFromEnd(offset)
+        }
+    }
+    pub fn any_field() -> Self {
+        Self::AnyField
+    }
+    pub fn any_item() -> Self {
+        Self::AnyItem
+    }
+    pub fn recursive_descent() -> Self {
+        Self::RecursiveDescent
+    }
+    pub fn slice(start: Option<isize>, end: Option<isize>, step: usize) -> Self {
+        Self::Slice { start, end, step }
+    }
+    pub fn filter(predicate: Predicate) -> Self {
+        Self::Filter(predicate)
+    }
+    pub fn union(branches: Vec<JSONQuery>) -> Self {
+        Self::Union(branches)
+    }
+    pub fn object(entries: Vec<(String, JSONQuery)>) -> Self {
+        Self::Object(entries)
+    }
+    /// A singular element can only ever match one location in its parent (or build one object from it).
+    pub fn is_singular(&self) -> bool {
+        match self {
+            QueryElement::Field(_)
+            | QueryElement::ArrayItem(_)
+            | QueryElement::IndexOrKey(_)
+            | QueryElement::ArrayItemFromEnd(_)
+            | QueryElement::Object(_) => true,
+            QueryElement::AnyField
+            | QueryElement::AnyItem
+            | QueryElement::RecursiveDescent
+            | QueryElement::Slice { .. }
+            | QueryElement::Filter(_)
+            | QueryElement::Union(_) => false,
+        }
+    }
+    /// Does this element need the length of a sequence, to count back from its end?
+    pub(crate) fn counts_from_end(&self) -> bool {
+        match self {
+            QueryElement::ArrayItemFromEnd(_) => true,
+            QueryElement::Slice { start, end, .. } => {
+                start.is_some_and(|start| start < 0) || end.is_some_and(|end| end < 0)
+            }
+            _ => false,
+        }
+    }
+    /// Does this element of a query accept a concrete element of the current path?
+    /// The ``length`` of the enclosing sequence is needed to resolve negative positions.
+    ///
+    /// RecursiveDescent, Filter, Union and Object are handled by the executor, since they need more context.
+    pub(crate) fn matches(&self, found: &QueryElement, length: Option<usize>) -> bool {
+        match (self, found) {
+            (QueryElement::Field(field), QueryElement::Field(name)) => field == name,
+            (QueryElement::ArrayItem(x), QueryElement::ArrayItem(index)) => x == index,
+            (QueryElement::IndexOrKey(x), QueryElement::ArrayItem(index)) => x == index,
+            (QueryElement::IndexOrKey(x), QueryElement::Field(name)) => *name == x.to_string(),
+            (QueryElement::ArrayItemFromEnd(offset), QueryElement::ArrayItem(index)) => {
+                match length {
+                    Some(length) => *offset <= length && *index == length - offset,
+                    None => false,
+                }
+            }
+            (
+                QueryElement::AnyField | QueryElement::AnyItem,
+                QueryElement::Field(_) | QueryElement::ArrayItem(_),
+            ) => true,
+            (QueryElement::Slice { start, end, step }, QueryElement::ArrayItem(index)) => {
+                slice_contains(*start, *end, *step, *index, length)
+            }
+            _ => false,
+        }
+    }
+    /// Could this element match a sequence element after ``index``? This lets us stop early, so when unsure we say yes.
+    pub(crate) fn matches_after(&self, index: usize, length: Option<usize>) -> bool {
+        let more = length.is_none_or(|length| index + 1 < length);
+        match self {
+            QueryElement::ArrayItem(x) | QueryElement::IndexOrKey(x) => {
+                *x > index && length.is_none_or(|length| *x < length)
+            }
+            QueryElement::ArrayItemFromEnd(offset) => {
+                length.is_none_or(|length| *offset <= length && length - offset > index)
+            }
+            QueryElement::Slice { end: Some(end), .. } => {
+                more && slice_bound(*end, length).is_none_or(|end| index + 1 < end)
+            }
+            QueryElement::AnyField
+            | QueryElement::AnyItem
+            | QueryElement::RecursiveDescent
+            | QueryElement::Slice { .. }
+            | QueryElement::Filter(_) => more,
+            QueryElement::Field(_) | QueryElement::Union(_) | QueryElement::Object(_) => false,
+        }
+    }
+}
+
+/// Field names with these characters (or no characters at all) must be quoted to parse back.
+fn needs_quotes(name: &str) -> bool {
+    name.is_empty()
+        || name
+            .chars()
+            .any(|ch| ch.is_whitespace() || ch.is_control() || ".[]\"*()=!<>,{}".contains(ch))
+}
+
+/// Keys of an object construction can be written bare if they look like identifiers.
+pub(crate) fn is_bare_key(ch: char) -> bool {
+    ch.is_alphanumeric() || ch == '_' || ch == '-'
+}
+
+/// Resolve a (possibly negative) slice bound against the sequence length, clamping like Python does.
+fn slice_bound(bound: isize, length: Option<usize>) -> Option<usize> {
+    if bound >= 0 {
+        Some(match length {
+            Some(length) => (bound as usize).min(length),
+            None => bound as usize,
+        })
+    } else {
+        length.map(|length| length.saturating_sub(bound.unsigned_abs()))
+    }
+}
+
+fn slice_contains(
+    start: Option<isize>,
+    end: Option<isize>,
+    step: usize,
+    index: usize,
+    length: Option<usize>,
+) -> bool {
+    let start = match start {
+        Some(start) => slice_bound(start, length),
+        None => Some(0),
+    };
+    let end = match end {
+        Some(end) => slice_bound(end, length),
+        None => Some(usize::MAX),
+    };
+    match (start, end) {
+        (Some(start), Some(end)) => {
+            index >= start && index < end && (index - start).is_multiple_of(step.max(1))
+        }
+        _ => false,
+    }
+}
+
+/// Branches of a Union or Object print ``@`` when they are empty, so they can still be parsed back.
+fn write_branch(f: &mut std::fmt::Formatter<'_>, branch: &JSONQuery) -> std::fmt::Result {
+    if branch.elements.is_empty() {
+        write!(f, "@")
+    } else {
+        write!(f, "{}", branch)
+    }
 }
 
 impl std::fmt::Display for QueryElement {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
+            QueryElement::Field(name) if needs_quotes(name) => {
+                write!(f, ".{}", serde_json::Value::from(name.as_str()))
+            }
             QueryElement::Field(name) => write!(f, ".{}", name),
-            QueryElement::ArrayItem(index) => write!(f, "[{}]", index),
+            QueryElement::ArrayItem(index) | QueryElement::IndexOrKey(index) => {
+                write!(f, "[{}]", index)
+            }
+            QueryElement::ArrayItemFromEnd(offset) => write!(f, "[-{}]", offset),
+            QueryElement::AnyField => write!(f, ".*"),
+            QueryElement::AnyItem => write!(f, "[*]"),
+            QueryElement::RecursiveDescent => write!(f, ".."),
+            QueryElement::Slice { start, end, step } => {
+                write!(f, "[")?;
+                if let Some(start) = start {
+                    write!(f, "{}", start)?;
+                }
+                write!(f, ":")?;
+                if let Some(end) = end {
+                    write!(f, "{}", end)?;
+                }
+                if *step != 1 {
+                    write!(f, ":{}", step)?;
+                }
+                write!(f, "]")
+            }
+            QueryElement::Filter(predicate) => write!(f, "[?({})]", predicate),
+            QueryElement::Union(branches) => {
+                write!(f, "(")?;
+                for (i, branch) in branches.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    write_branch(f, branch)?;
+                }
+                write!(f, ")")
+            }
+            QueryElement::Object(entries) => {
+                write!(f, "{{")?;
+                for (i, (key, value)) in entries.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    if !key.is_empty() && key.chars().all(is_bare_key) {
+                        write!(f, "{}: ", key)?;
+                    } else {
+                        write!(f, "{}: ", serde_json::Value::from(key.as_str()))?;
+                    }
+                    write_branch(f, value)?;
+                }
+                write!(f, "}}")
+            }
         }
     }
 }
 
 /// This is the main interface to this library.
 /// Create a new JSONQuery by calling parse.
-#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+#[derive(Clone, PartialEq, Eq, Debug, Hash, Serialize, Deserialize)]
 pub struct JSONQuery {
     /// A list of access-elements, field names or array indices.
     pub elements: Vec<QueryElement>,
@@ -38,8 +283,16 @@ pub struct JSONQuery {
 /// This is a way to visualize a JSONQuery object as a parse-able string.
 impl std::fmt::Display for JSONQuery {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for elem in self.elements.iter() {
-            write!(f, "{}", elem)?
+        let mut elements = self.elements.iter().peekable();
+        while let Some(elem) = elements.next() {
+            match (elem, elements.peek()) {
+                // ``..name`` shares its dot with the field that follows it.
+                (
+                    QueryElement::RecursiveDescent,
+                    Some(QueryElement::Field(_)) | Some(QueryElement::AnyField),
+                ) => write!(f, ".")?,
+                _ => write!(f, "{}", elem)?,
+            }
         }
         Ok(())
     }
@@ -47,7 +300,7 @@ impl std::fmt::Display for JSONQuery {
 
 impl JSONQuery {
     /// Construct a new JSONQuery object from discrete elements.
-    pub(crate) fn new(elements: Vec<QueryElement>) -> Self {
+    pub fn new(elements: Vec<QueryElement>) -> Self {
         Self { elements }
     }
 
@@ -67,13 +320,139 @@ impl JSONQuery {
         Ok(Self::new(parse_query(input)?))
     }
 
-    #[cfg(test)]
+    /// Construct a new JSONQuery object from an RFC 6901 JSON Pointer.
+    ///
+    /// Reference tokens that look like array indices become ``IndexOrKey`` elements, since a pointer can't say which it means;
+    /// everything else is a ``Field``.
+    ///
+    /// ```
+    /// use access_json::JSONQuery;
+    /// use serde_json::json;
+    ///
+    /// let query = JSONQuery::from_json_pointer("/items/0/a~1b").unwrap();
+    /// let list = json!({"items": [{"a/b": 1}]});
+    /// let map = json!({"items": {"0": {"a/b": 2}}});
+    /// assert_eq!(Some(json!(1)), query.execute(&list).unwrap());
+    /// assert_eq!(Some(json!(2)), query.execute(&map).unwrap());
+    /// ```
+    pub fn from_json_pointer(input: &str) -> Result<Self, QueryParseErr> {
+        Ok(Self::new(parse_json_pointer(input)?))
+    }
+
+    /// Write this query as an RFC 6901 JSON Pointer, if it only uses fields and non-negative array indices.
+    ///
+    /// A pointer can't tell an index from a key that looks like one: ``[0]`` and ``."0"`` both become ``/0``,
+    /// which ``from_json_pointer`` reads back as ``IndexOrKey(0)``.
+    ///
+    /// ```
+    /// use access_json::JSONQuery;
+    ///
+    /// let query = JSONQuery::parse(r#".items[0]."a/b""#).unwrap();
+    /// assert_eq!(Some("/items/0/a~1b".to_string()), query.to_json_pointer());
+    /// assert_eq!(None, JSONQuery::parse(".items[*]").unwrap().to_json_pointer());
+    /// ```
+    pub fn to_json_pointer(&self) -> Option<String> {
+        let mut pointer = String::new();
+        for elem in self.elements.iter() {
+            pointer.push('/');
+            match elem {
+                QueryElement::Field(name) => {
+                    pointer.push_str(&name.replace('~', "~0").replace('/', "~1"))
+                }
+                QueryElement::ArrayItem(index) | QueryElement::IndexOrKey(index) => {
+                    pointer.push_str(&index.to_string())
+                }
+                _ => return None,
+            }
+        }
+        Some(pointer)
+    }
+
+    /// Construct a new JSONQuery object from an RFC 9535 JSONPath expression.
+    ///
+    /// JSONPath that we cannot execute, e.g., ``&&`` or functions inside filters, gives ``QueryParseErr::Unsupported``.
+    ///
+    /// ```
+    /// use access_json::JSONQuery;
+    ///
+    /// let query = JSONQuery::parse_jsonpath("$.store.book[?@.price < 10]['title']").unwrap();
+    /// assert_eq!(query, JSONQuery::parse(".store.book[?(.price < 10)].title").unwrap());
+    /// ```
+    pub fn parse_jsonpath(input: &str) -> Result<Self, QueryParseErr> {
+        Ok(Self::new(parse_jsonpath(input)?))
+    }
+
+    /// Construct a new JSONQuery object with just one element.
     pub fn single(q: QueryElement) -> Self {
         Self::new(vec![q])
     }
 
+    /// The empty query, which finds the whole target; build on it without formatting and parsing a string.
+    ///
+    /// Field names are taken as they are, so dots, spaces and quotes need no escaping.
+    ///
+    /// ```
+    /// use access_json::JSONQuery;
+    ///
+    /// let query = JSONQuery::root().field("items").index(3).field("first name");
+    /// assert_eq!(query, JSONQuery::parse(r#".items[3]."first name""#).unwrap());
+    /// assert_eq!(Some(JSONQuery::root().field("items").index(3)), query.parent());
+    /// ```
+    pub fn root() -> Self {
+        Self::new(Vec::new())
+    }
+
+    /// This query, followed by a field.
+    pub fn field(mut self, name: &str) -> Self {
+        self.push(QueryElement::field(name));
+        self
+    }
+
+    /// This query, followed by an array index.
+    pub fn index(mut self, index: usize) -> Self {
+        self.push(QueryElement::array_item(index));
+        self
+    }
+
+    /// Add an element to the end of this query.
+    pub fn push(&mut self, elem: QueryElement) {
+        self.elements.push(elem);
+    }
+
+    /// Remove the last element of this query, if there is one.
+    pub fn pop(&mut self) -> Option<QueryElement> {
+        self.elements.pop()
+    }
+
+    /// This query, followed by every element of ``rest``; e.g., a query relative to what this one finds.
+    ///
+    /// ```
+    /// use access_json::JSONQuery;
+    ///
+    /// let base = JSONQuery::parse(".users[*]").unwrap();
+    /// let rest = JSONQuery::parse(".name").unwrap();
+    /// assert_eq!(JSONQuery::parse(".users[*].name").unwrap(), base.join(&rest));
+    /// ```
+    pub fn join(&self, rest: &JSONQuery) -> Self {
+        Self::new([&self.elements[..], &rest.elements[..]].concat())
+    }
+
+    /// This query without its last element; None for the root.
+    pub fn parent(&self) -> Option<Self> {
+        let (_, parent) = self.elements.split_last()?;
+        Some(Self::new(parent.to_vec()))
+    }
+
+    /// A query without wildcards, recursive descent or unions finds at most one value; otherwise we return an array of every match.
+    pub fn is_singular(&self) -> bool {
+        self.elements.iter().all(|e| e.is_singular())
+    }
+
     /// Execute a JSONQuery object against any serde-serializable object.
     ///
+    /// Queries with wildcards (``.*``, ``[*]``, ``..``, slices, filters or unions) always return ``Some`` JSON array holding every match.
+    /// No matter how many paths a query has, the target is only serialized once; see ``QuerySet`` to run many queries at once.
+    ///
     /// ```
     /// use access_json::JSONQuery;
     /// use std::collections::HashMap;
@@ -95,8 +474,82 @@ impl JSONQuery {
         &self,
         target: &dyn AnySerializable,
     ) -> Result<Option<serde_json::Value>, QueryExecErr> {
-        let mut runner = QueryExecutor::new(self)?;
-        target.serialize(&mut runner)?;
+        self.execute_with(target, &ExecOptions::default())
+    }
+
+    /// Execute a JSONQuery object with non-default settings, e.g., to give byte arrays as base64 strings.
+    ///
+    /// ```
+    /// use access_json::{BytesFormat, ExecOptions, JSONQuery};
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let options = ExecOptions { bytes: BytesFormat::Hex };
+    /// let data = serde_json::json!({"id": 7});
+    /// assert_eq!(Some(7.into()), JSONQuery::parse(".id")?.execute_with(&data, &options)?);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn execute_with(
+        &self,
+        target: &dyn AnySerializable,
+        options: &ExecOptions,
+    ) -> Result<Option<serde_json::Value>, QueryExecErr> {
+        let mut runner = QueryExecutor::new(std::slice::from_ref(self), options)?;
+        runner.run(target)?;
         Ok(runner.get_result())
     }
+
+    /// Execute a JSONQuery object, and give every value it finds together with the concrete path where it was found.
+    ///
+    /// Wildcards, slices, filters and ``..`` are replaced by the actual fields and indices; no match gives an empty list.
+    ///
+    /// ```
+    /// use access_json::JSONQuery;
+    /// use serde_json::json;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let data = json!({"pets": [{"name": "Rex"}, {"name": "Tom", "age": 3}]});
+    ///
+    /// let found = JSONQuery::parse(".pets[*].age")?.execute_with_paths(&data)?;
+    /// assert_eq!(vec![(JSONQuery::parse(".pets[1].age")?, json!(3))], found);
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn execute_with_paths(
+        &self,
+        target: &dyn AnySerializable,
+    ) -> Result<Vec<(JSONQuery, serde_json::Value)>, QueryExecErr> {
+        let mut runner = QueryExecutor::new(std::slice::from_ref(self), &ExecOptions::default())?;
+        runner.run(target)?;
+        Ok(runner.get_paths().into_iter().next().unwrap_or_default())
+    }
+
+    /// Execute this query and deserialize what it finds into a ``T``, instead of a ``serde_json::Value``.
+    ///
+    /// If the result does not fit ``T``, we give ``QueryExecErr::Deserialization`` with this query in it.
+    ///
+    /// ```
+    /// use access_json::JSONQuery;
+    /// use serde_json::json;
+    ///
+    /// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+    /// let data = json!({"sizes": [3, 4], "name": "box"});
+    ///
+    /// let sizes: Option<Vec<u32>> = JSONQuery::parse(".sizes")?.execute_as(&data)?;
+    /// assert_eq!(Some(vec![3, 4]), sizes);
+    /// assert!(JSONQuery::parse(".name")?.execute_as::<u32>(&data).is_err());
+    /// # Ok(())
+    /// # }
+    /// ```
+    pub fn execute_as<T>(&self, target: &dyn AnySerializable) -> Result<Option<T>, QueryExecErr>
+    where
+        T: DeserializeOwned,
+    {
+        match self.execute(target)? {
+            None => Ok(None),
+            Some(found) => serde_json::from_value(found)
+                .map(Some)
+                .map_err(|err| QueryExecErr::Deserialization(self.clone(), err.to_string())),
+        }
+    }
 }
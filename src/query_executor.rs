@@ -1,5 +1,10 @@
+// The serde impls below keep the ``impl<'a>`` lifetimes and ``<T: ?Sized>`` bounds that older serde signatures used.
+#![allow(clippy::needless_lifetimes, clippy::multiple_bound_locations)]
+
+use crate::paths::JSONType;
 use crate::query::{JSONQuery, QueryElement};
 use crate::AnySerializable;
+use serde::Serialize;
 use serde_json::Value as JSON;
 
 #[derive(Serialize, Deserialize, Debug, PartialEq)]
@@ -10,331 +15,1018 @@ enum State {
     MapKey,
     /// When we have encountered a Str in MapKey state.
     MapKeyStr(String),
+    /// A key we haven't entered yet, because a filter has to see its value first.
+    PendingKey(String),
     /// When we have the name of the field and begin serializing/visiting the MapValue.
     MapValue,
-    /// Keep track of where we are, index of length:
-    Sequence(usize, usize),
+    /// Keep track of where we are, index of length (if the sequence told us its length up front):
+    Sequence(usize, Option<usize>),
+    /// The fields of a tuple or struct variant so far; a filter has to see all of them before we know whether to enter it.
+    Buffered(String, JSON),
 }
 
 #[derive(PartialEq, Eq, Debug, Serialize, Deserialize)]
 enum ElementKind {
-    Root,
     List,
     Map,
 }
 
+/// Where a match belongs: which group, which of its lists, and which index in that list.
+type Slot = (usize, usize, usize);
+
 #[derive(Debug, Serialize, Deserialize)]
 pub struct OutputStackFrame {
     kind: ElementKind,
+    /// If this sub-tree is itself a match, where its value belongs.
+    slots: Vec<Slot>,
     list_items: Vec<JSON>,
     map_keys: Vec<String>,
     map_values: Vec<JSON>,
 }
 
-impl Default for OutputStackFrame {
-    fn default() -> Self {
+impl OutputStackFrame {
+    fn new(kind: ElementKind, slots: Vec<Slot>) -> OutputStackFrame {
         Self {
-            kind: ElementKind::Root,
+            kind,
+            slots,
             list_items: Vec::new(),
             map_keys: Vec::new(),
             map_values: Vec::new(),
         }
     }
-}
-impl OutputStackFrame {
-    fn list() -> OutputStackFrame {
-        Self {
-            kind: ElementKind::List,
-            ..Default::default()
-        }
-    }
-    fn map() -> OutputStackFrame {
-        Self {
-            kind: ElementKind::Map,
-            ..Default::default()
-        }
-    }
-    fn push_item(&mut self, item: JSON) {
-        debug_assert_ne!(self.kind, ElementKind::Map);
-        self.kind = ElementKind::List;
-        self.list_items.push(item);
-    }
     fn push_key(&mut self, item: String) {
-        debug_assert_ne!(self.kind, ElementKind::List);
-        self.kind = ElementKind::Map;
+        debug_assert_eq!(self.kind, ElementKind::Map);
         self.map_keys.push(item);
     }
-    fn push_value(&mut self, item: JSON) {
-        debug_assert_ne!(self.kind, ElementKind::List);
-        self.kind = ElementKind::Map;
-        self.map_values.push(item);
-    }
-    /// When we've wrapped the level above us, hope we know what type of thing we are!
-    fn push(&mut self, complex: OutputStackFrame) {
-        let value = complex.finish();
+    /// List and Map frames are sub-trees of a match in progress.
+    fn push(&mut self, value: JSON) {
         match self.kind {
-            ElementKind::Root => self.push_item(value),
-            ElementKind::List => self.push_item(value),
-            ElementKind::Map => self.push_value(value),
+            ElementKind::List => self.list_items.push(value),
+            ElementKind::Map => self.map_values.push(value),
         }
     }
     fn finish(self) -> JSON {
         match self.kind {
-            ElementKind::Root => panic!("What's a ROOT? {:?}", self),
             ElementKind::List => JSON::Array(self.list_items),
             ElementKind::Map => {
-                debug_assert_eq!(self.map_values.len(), self.map_values.len());
-                let dict: serde_json::Map<String, JSON> = self
-                    .map_keys
-                    .into_iter()
-                    .zip(self.map_values.into_iter())
-                    .collect();
+                debug_assert_eq!(self.map_keys.len(), self.map_values.len());
+                let dict: serde_json::Map<String, JSON> =
+                    self.map_keys.into_iter().zip(self.map_values).collect();
                 JSON::Object(dict)
             }
         }
     }
 }
 
-enum NextStep<'a> {
-    NotMatching,
-    Found(&'a QueryElement),
-    IsMatch(&'a [QueryElement]),
+/// One node of the trie that every query is compiled into; queries with the same prefix share its nodes.
+#[derive(Debug, Serialize, Deserialize)]
+struct Node {
+    /// The element that leads here from the parent node; None for the root of a Fork.
+    element: Option<QueryElement>,
+    children: Vec<usize>,
+    /// The branches of the enclosing Fork that end here, and the Fork each one starts next, if any.
+    ends: Vec<(usize, Option<usize>)>,
+}
+
+impl Node {
+    fn new(element: Option<QueryElement>) -> Node {
+        Self {
+            element,
+            children: Vec::new(),
+            ends: Vec::new(),
+        }
+    }
+}
+
+/// Branches that all start from the same location: a Union, an Object, or every query we run at once.
+#[derive(Debug, Serialize, Deserialize)]
+struct Fork {
+    /// The trie node that every branch starts from.
+    root: usize,
+    /// The key of each branch if this builds an Object.
+    keys: Option<Vec<String>>,
+    /// Does each branch give at most one value?
+    singular: Vec<bool>,
+}
+
+/// The values of one Fork, started from one location in the document.
+/// Group zero holds the results of every query.
+#[derive(Debug, Serialize, Deserialize)]
+struct Group {
+    fork: usize,
+    /// Where in the document this group was started.
+    path: Vec<QueryElement>,
+    /// One list of items for each branch, in document order.
+    lists: Vec<Vec<Item>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+enum Item {
+    /// A match, and the path where we found it.
+    Value(JSON, Vec<QueryElement>),
+    Group(usize),
+}
+
+/// Which trie node we reached, and the group its matches belong to.
+type Position = (usize, usize);
+
+/// How byte arrays, e.g., ``serde_bytes::ByteBuf`` fields, appear in results.
+///
+/// Whichever you choose, ``[n]`` still finds the n-th byte as a number.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub enum BytesFormat {
+    /// An array of numbers, like serde_json gives.
+    #[default]
+    Array,
+    /// A standard, padded base64 string.
+    Base64,
+    /// A lowercase hex string.
+    Hex,
+}
+
+const BASE64_ALPHABET: &[u8; 64] =
+    b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+
+impl BytesFormat {
+    /// Write ``bytes`` as a string, unless they should stay an array.
+    fn encode(&self, bytes: &[u8]) -> Option<String> {
+        match self {
+            BytesFormat::Array => None,
+            BytesFormat::Base64 => {
+                let mut text = String::with_capacity(bytes.len().div_ceil(3) * 4);
+                for chunk in bytes.chunks(3) {
+                    let group = chunk
+                        .iter()
+                        .enumerate()
+                        .fold(0u32, |acc, (i, byte)| acc | (*byte as u32) << (16 - 8 * i));
+                    for i in 0..4 {
+                        if i <= chunk.len() {
+                            let sextet = (group >> (18 - 6 * i)) & 0x3f;
+                            text.push(BASE64_ALPHABET[sextet as usize] as char);
+                        } else {
+                            text.push('=');
+                        }
+                    }
+                }
+                Some(text)
+            }
+            BytesFormat::Hex => Some(bytes.iter().map(|byte| format!("{:02x}", byte)).collect()),
+        }
+    }
+}
+
+/// Settings for executing queries; the defaults give the same values serde_json would.
+#[derive(Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub struct ExecOptions {
+    pub bytes: BytesFormat,
+}
+
+#[derive(Serialize, Deserialize, Debug)]
+struct Listing {
+    max_depth: Option<usize>,
+    found: Vec<(Vec<QueryElement>, Option<JSONType>)>,
+    /// The entry whose value we are about to see, so we can fill in its type.
+    pending: Option<usize>,
 }
 
 #[derive(Serialize, Deserialize, Debug)]
 pub struct QueryExecutor {
-    query: Vec<QueryElement>,
+    nodes: Vec<Node>,
+    forks: Vec<Fork>,
     current_path: Vec<QueryElement>,
+    /// For each prefix of current_path, every position in the query it could have reached.
+    matching: Vec<Vec<Position>>,
+    /// Filter nodes that the sequence element being entered passed, for each group.
+    accepted: Vec<Position>,
+    /// For each element of current_path, could a later sibling of it still lead to a match?
+    later: Vec<bool>,
+    /// For each prefix of current_path, the fields of it that we already visited.
+    seen: Vec<Vec<String>>,
+    /// Set once no more matches are possible, so that we can stop serializing.
+    stopped: bool,
+    /// Set when the rest of some child cannot match; we skip ahead to the next sibling once we're back at this depth.
+    resume: Option<usize>,
+    /// The first error we raised ourselves; erased_serde can only hand it back to us as a message.
+    failure: Option<QueryExecErr>,
+    /// When listing paths instead of running queries: how deep to go, and every path we entered with its type.
+    listing: Option<Listing>,
     state: Vec<State>,
+    groups: Vec<Group>,
     output: Vec<OutputStackFrame>,
+    options: ExecOptions,
 }
 impl QueryExecutor {
-    pub fn new(query: &JSONQuery) -> Result<Self, QueryExecErr> {
-        Ok(Self {
-            query: query.elements.clone(),
+    /// Prepare to run every query in one pass; results come back in the same order.
+    pub fn new(queries: &[JSONQuery], options: &ExecOptions) -> Result<Self, QueryExecErr> {
+        let mut runner = Self {
+            nodes: Vec::new(),
+            forks: Vec::new(),
             current_path: Vec::new(),
+            matching: Vec::new(),
+            accepted: Vec::new(),
+            later: Vec::new(),
+            seen: vec![Vec::new()],
+            stopped: false,
+            resume: None,
+            failure: None,
+            listing: None,
             state: Vec::new(),
-            // Keep a list on the bottom of the stack for single-value answers.
-            output: vec![Default::default()],
-        })
+            groups: Vec::new(),
+            output: Vec::new(),
+            options: options.clone(),
+        };
+        let fork = runner.compile_fork(
+            None,
+            queries.iter().map(|query| query.elements.clone()).collect(),
+        );
+        runner.groups.push(Group {
+            fork,
+            path: Vec::new(),
+            lists: queries.iter().map(|_| Vec::new()).collect(),
+        });
+        let start = runner.expand(vec![(runner.forks[fork].root, 0)]);
+        runner.matching.push(start);
+        Ok(runner)
+    }
+    /// Instead of matching queries, enter everything up to ``max_depth`` and remember every path.
+    pub fn list_paths(&mut self, max_depth: Option<usize>) {
+        self.listing = Some(Listing {
+            max_depth,
+            found: vec![(Vec::new(), None)],
+            pending: Some(0),
+        });
+    }
+    /// Every path we entered while listing, in document order, with the type of its value; the first is the empty path.
+    pub fn get_listing(self) -> Vec<(JSONQuery, Option<JSONType>)> {
+        match self.listing {
+            Some(listing) => listing
+                .found
+                .into_iter()
+                .map(|(path, kind)| (JSONQuery::new(path), kind))
+                .collect(),
+            None => Vec::new(),
+        }
     }
-    fn next_step(&self) -> NextStep<'_> {
-        let mut i = 0;
-        while i < self.query.len() && i < self.current_path.len() {
-            if self.query[i] != self.current_path[i] {
-                return NextStep::NotMatching;
+    /// Are we listing paths, and is a path with ``depth`` elements shallow enough to list?
+    fn is_listing(&self, depth: usize) -> bool {
+        self.listing
+            .as_ref()
+            .is_some_and(|listing| listing.max_depth.is_none_or(|max| depth <= max))
+    }
+    /// The value at the path we just listed has started; note what type it is.
+    fn listed_type(&mut self, kind: JSONType) {
+        if let Some(listing) = &mut self.listing {
+            if let Some(entry) = listing.pending.take() {
+                listing.found[entry].1 = Some(kind);
             }
-            i += 1;
         }
-        // we have matched until one of us exhausted (query) or current_path.
-        if self.current_path.len() < self.query.len() {
-            NextStep::Found(&self.query[i])
-        } else {
-            NextStep::IsMatch(&self.current_path[i..])
+    }
+    /// Add a Fork whose branches start from a new trie root; returns the new Fork.
+    fn compile_fork(
+        &mut self,
+        keys: Option<Vec<String>>,
+        branches: Vec<Vec<QueryElement>>,
+    ) -> usize {
+        let root = self.nodes.len();
+        self.nodes.push(Node::new(None));
+        let fork = self.forks.len();
+        self.forks.push(Fork {
+            root,
+            keys,
+            singular: Vec::new(),
+        });
+        for (branch, elements) in branches.into_iter().enumerate() {
+            let singular = self.compile(elements, root, branch);
+            self.forks[fork].singular.push(singular);
+        }
+        fork
+    }
+    /// Add the path of one branch below ``node``, sharing nodes with the branches added before it.
+    /// A Union or Object starts a new Fork, and elements after it are appended to each of its branches.
+    ///
+    /// Returns whether this branch gives at most one value.
+    fn compile(&mut self, elements: Vec<QueryElement>, mut node: usize, branch: usize) -> bool {
+        let mut singular = true;
+        let mut elements = elements.into_iter();
+        loop {
+            let (keys, branches) = match elements.next() {
+                None => {
+                    self.nodes[node].ends.push((branch, None));
+                    return singular;
+                }
+                Some(QueryElement::Union(branches)) => {
+                    singular = false;
+                    (None, branches)
+                }
+                Some(QueryElement::Object(entries)) => {
+                    let (keys, branches) = entries.into_iter().unzip();
+                    (Some(keys), branches)
+                }
+                Some(elem) => {
+                    singular = singular && elem.is_singular();
+                    node = self.child(node, elem);
+                    continue;
+                }
+            };
+            let rest: Vec<QueryElement> = elements.collect();
+            let branches = branches
+                .into_iter()
+                .map(|branch: JSONQuery| [branch.elements, rest.clone()].concat())
+                .collect();
+            let fork = self.compile_fork(keys, branches);
+            self.nodes[node].ends.push((branch, Some(fork)));
+            return singular;
         }
     }
-    /// Find the relative path to our current location, but only if we're matching the query.
-    fn relative_path(&self) -> Option<Vec<QueryElement>> {
-        match self.next_step() {
-            NextStep::IsMatch(relative) => Some(relative.to_vec()),
+    /// Find or add the child of ``node`` reached by ``elem``.
+    fn child(&mut self, node: usize, elem: QueryElement) -> usize {
+        for &child in self.nodes[node].children.iter() {
+            if self.nodes[child].element.as_ref() == Some(&elem) {
+                return child;
+            }
+        }
+        self.nodes.push(Node::new(Some(elem)));
+        let child = self.nodes.len() - 1;
+        self.nodes[node].children.push(child);
+        child
+    }
+    /// A RecursiveDescent may match zero path elements, so we may already be at the node after it.
+    /// Reaching the end of a branch that starts a Fork adds a new group, with its branches starting here.
+    fn expand(&mut self, mut positions: Vec<Position>) -> Vec<Position> {
+        let mut i = 0;
+        while i < positions.len() {
+            let (node, group) = positions[i];
+            i += 1;
+            for &child in self.nodes[node].children.iter() {
+                let next = (child, group);
+                if self.nodes[child].element == Some(QueryElement::RecursiveDescent)
+                    && !positions.contains(&next)
+                {
+                    positions.push(next);
+                }
+            }
+            let ends = self.nodes[node].ends.clone();
+            for (branch, fork) in ends {
+                if let Some(fork) = fork {
+                    let started = self.groups.len();
+                    self.groups.push(Group {
+                        fork,
+                        path: self.current_path.clone(),
+                        lists: self.forks[fork]
+                            .singular
+                            .iter()
+                            .map(|_| Vec::new())
+                            .collect(),
+                    });
+                    self.groups[group].lists[branch].push(Item::Group(started));
+                    positions.push((self.forks[fork].root, started));
+                }
+            }
+        }
+        positions
+    }
+    /// Which query positions could we reach by stepping into the child ``found``?
+    fn step(&self, found: &QueryElement) -> Vec<Position> {
+        let length = match self.state.last() {
+            Some(State::Sequence(_, len)) => *len,
             _ => None,
+        };
+        let mut next = Vec::new();
+        for &(node, group) in self.matching.last().unwrap() {
+            // A RecursiveDescent swallows any number of path elements.
+            if self.nodes[node].element == Some(QueryElement::RecursiveDescent)
+                && !next.contains(&(node, group))
+            {
+                next.push((node, group));
+            }
+            for &child in self.nodes[node].children.iter() {
+                let reached = match &self.nodes[child].element {
+                    Some(QueryElement::RecursiveDescent) | None => continue,
+                    Some(QueryElement::Filter(_)) => self.accepted.contains(&(child, group)),
+                    Some(expected) => expected.matches(found, length),
+                };
+                if reached && !next.contains(&(child, group)) {
+                    next.push((child, group));
+                }
+            }
         }
+        next
+    }
+    /// Where every match at the current path belongs: which group, and which of its branches.
+    fn matched(&self) -> Vec<(usize, usize)> {
+        let mut matched = Vec::new();
+        for &(node, group) in self.matching.last().unwrap() {
+            for (branch, fork) in self.nodes[node].ends.iter() {
+                if fork.is_none() {
+                    matched.push((group, *branch));
+                }
+            }
+        }
+        matched
     }
+    /// Is the current path exactly something the query is looking for?
     fn is_match(&self) -> bool {
-        self.relative_path().is_some()
+        self.matching.last().unwrap().iter().any(|(node, _)| {
+            self.nodes[*node]
+                .ends
+                .iter()
+                .any(|(_, fork)| fork.is_none())
+        })
+    }
+    /// Are we inside the sub-tree of some match, building its output?
+    fn is_capturing(&self) -> bool {
+        !self.output.is_empty()
+    }
+    /// Hand a finished value to the innermost output frame, and to every group it is a match for.
+    fn emit(&mut self, value: JSON, slots: Vec<Slot>) {
+        for (group, branch, index) in slots {
+            if let Item::Value(found, _) = &mut self.groups[group].lists[branch][index] {
+                *found = value.clone();
+            }
+        }
+        if let Some(frame) = self.output.last_mut() {
+            frame.push(value);
+        }
+    }
+    /// Reserve a spot for each new match, so that matches stay in document order even if they are nested.
+    fn new_slots(&mut self) -> Vec<Slot> {
+        let mut slots = Vec::new();
+        for (group, branch) in self.matched() {
+            let list = &mut self.groups[group].lists[branch];
+            list.push(Item::Value(JSON::Null, self.current_path.clone()));
+            slots.push((group, branch, list.len() - 1));
+        }
+        slots
+    }
+    /// Containers can't be map keys: serde_json would refuse them, and we can't name them in a query.
+    fn check_not_key(&mut self) -> Result<(), QueryExecErr> {
+        match self.state.last() {
+            Some(State::MapKey) => {
+                Err(self.fail(QueryExecErr::BadMapKey(format!("{:?}", self.current_path))))
+            }
+            _ => Ok(()),
+        }
     }
     fn possible_result(&mut self, found: &dyn AnySerializable) -> Result<(), QueryExecErr> {
-        if self.is_match() {
-            let output_frame = self.output.last_mut().unwrap();
-            match self.state.last().unwrap() {
-                State::MapKey | State::MapKeyStr(_) => panic!(
-                    "Shouldn't call possible_result here! {:?}, {:?}",
-                    self.state, self.current_path
-                ),
-                // StartMap is the state in which we visit struct fields.
-                State::StartMap | State::MapValue => {
-                    output_frame.push_value(serde_json::to_value(found)?)
+        if let Some(State::MapKey) = self.state.last() {
+            // Like serde_json, we write numbers, bools and chars as strings when they are keys.
+            let key = match serde_json::to_value(found)? {
+                JSON::String(key) => key,
+                JSON::Number(key) => key.to_string(),
+                JSON::Bool(key) => key.to_string(),
+                _ => {
+                    return Err(
+                        self.fail(QueryExecErr::BadMapKey(format!("{:?}", self.current_path)))
+                    )
                 }
-                State::Sequence(_, _) => output_frame.push_item(serde_json::to_value(found)?),
             };
+            self.map_key(&key);
+            return Ok(());
+        }
+        if let Some(State::MapKeyStr(_) | State::PendingKey(_)) = self.state.last() {
+            return Err(self.fail(QueryExecErr::InternalError(format!(
+                "Map key not a simple String! {:?}",
+                self.current_path
+            ))));
+        }
+        if self.listing.is_some() {
+            let value = serde_json::to_value(found)?;
+            self.listed_type(JSONType::of(&value));
+        }
+        if self.is_match() || self.is_capturing() {
+            let value = serde_json::to_value(found)?;
+            let slots = self.new_slots();
+            self.emit(value, slots);
         }
         Ok(())
     }
+    /// Serialize ``target`` through this executor; stopping early because nothing else can match is not an error.
+    pub fn run(&mut self, target: &dyn AnySerializable) -> Result<(), QueryExecErr> {
+        match target.serialize(&mut *self) {
+            // The error has been through erased_serde, so we can only recognize it by our flag.
+            Err(_) if self.stopped => Ok(()),
+            Err(err) => Err(self.failure.take().unwrap_or(err)),
+            result => result,
+        }
+    }
+    /// Remember an error of ours, so that ``run`` can return it intact.
+    fn fail(&mut self, err: QueryExecErr) -> QueryExecErr {
+        self.failure.get_or_insert(err).clone()
+    }
+    /// Could a child of the current scope that comes after ``found`` still lead to a match?
+    fn can_match_after(&self, found: &QueryElement) -> bool {
+        if self.is_listing(self.current_path.len() + 1) {
+            return true;
+        }
+        let length = match self.state.last() {
+            Some(State::Sequence(_, len)) => *len,
+            _ => None,
+        };
+        let seen = self.seen.last().unwrap();
+        let later = |elem: &QueryElement| match (elem, found) {
+            (_, QueryElement::ArrayItem(index)) => elem.matches_after(*index, length),
+            (QueryElement::IndexOrKey(x), _) => {
+                let key = x.to_string();
+                !elem.matches(found, length) && !seen.contains(&key)
+            }
+            (QueryElement::Field(name), _) => elem != found && !seen.contains(name),
+            (
+                QueryElement::AnyField
+                | QueryElement::AnyItem
+                | QueryElement::RecursiveDescent
+                | QueryElement::Filter(_),
+                _,
+            ) => true,
+            _ => false,
+        };
+        self.matching.last().unwrap().iter().any(|(node, _)| {
+            let node = &self.nodes[*node];
+            // A RecursiveDescent can swallow later siblings too.
+            let swallows = node.element == Some(QueryElement::RecursiveDescent);
+            (swallows && later(&QueryElement::RecursiveDescent))
+                || node
+                    .children
+                    .iter()
+                    .any(|child| self.nodes[*child].element.as_ref().is_some_and(later))
+        })
+    }
+    /// We're done with the child ``found``; if nothing after it can match, skip to where something still can.
+    ///
+    /// We skip by returning an error; serde passes it up to ``visit`` (or ``run``, if nothing at all can match).
+    /// It is recognized by ``resume`` and ``stopped``, so it must not go through ``fail``: a real error later in the run would be hidden by it.
+    fn stop_if_done(&mut self, found: &QueryElement) -> Result<(), QueryExecErr> {
+        if self.is_capturing() || self.can_match_after(found) {
+            return Ok(());
+        }
+        match self.later.iter().rposition(|later| *later) {
+            Some(depth) => self.resume = Some(depth + 1),
+            None => self.stopped = true,
+        }
+        Err(QueryExecErr::Serialization(
+            "Stopped early; no more matches are possible.".into(),
+        ))
+    }
+    /// Serialize the value of a child we entered; if we're skipping the rest of it, recover here.
+    fn visit<T: ?Sized>(&mut self, value: &T) -> Result<(), QueryExecErr>
+    where
+        T: serde::ser::Serialize,
+    {
+        let depth = self.current_path.len();
+        let state = self.state.len();
+        match value.serialize(&mut *self) {
+            Err(_) if self.resume == Some(depth) => {
+                self.resume = None;
+                self.state.truncate(state);
+                self.current_path.truncate(depth);
+                self.matching.truncate(depth + 1);
+                self.later.truncate(depth);
+                self.seen.truncate(depth + 1);
+                self.accepted.clear();
+                Ok(())
+            }
+            // Keep the first real error intact; on its way up it only passes through serde as a message.
+            Err(err) if self.resume.is_none() && !self.stopped => Err(self.fail(err)),
+            result => result,
+        }
+    }
+    /// The result of each query, in the order they were given to us.
+    pub fn get_results(self) -> Vec<Option<JSON>> {
+        let singular = self.forks[0].singular.clone();
+        self.get_paths()
+            .into_iter()
+            .zip(singular)
+            .map(|(found, singular)| {
+                let mut values = found.into_iter().map(|(_, value)| value);
+                if singular {
+                    values.next()
+                } else {
+                    Some(JSON::Array(values.collect()))
+                }
+            })
+            .collect()
+    }
     pub fn get_result(self) -> Option<JSON> {
-        debug_assert_eq!(self.output.len(), 1);
-        let output = &self.output[0];
-        match output.kind {
-            ElementKind::Root => output.list_items.get(0).cloned(),
-            ElementKind::List => output.list_items.get(0).cloned(),
-            ElementKind::Map => output.map_values.get(0).cloned(),
+        self.get_results().into_iter().next().flatten()
+    }
+    /// For each query, every value it found and the concrete path where we found it; an Object's path is where we started building it.
+    pub fn get_paths(mut self) -> Vec<Vec<(JSONQuery, JSON)>> {
+        debug_assert!(self.output.is_empty());
+        let singular = std::mem::take(&mut self.forks[0].singular);
+        let mut results = Vec::new();
+        for (branch, singular) in singular.into_iter().enumerate() {
+            let mut found = self.values(0, branch);
+            if singular {
+                found.truncate(1);
+            }
+            results.push(
+                found
+                    .into_iter()
+                    .map(|(path, value)| (JSONQuery::new(path), value))
+                    .collect(),
+            );
         }
+        results
+    }
+    /// Every value in one list of a group, with its path; a Union's values are spliced in, and an Object becomes one value.
+    fn values(&mut self, group: usize, branch: usize) -> Vec<(Vec<QueryElement>, JSON)> {
+        let mut values = Vec::new();
+        for item in std::mem::take(&mut self.groups[group].lists[branch]) {
+            match item {
+                Item::Value(value, path) => values.push((path, value)),
+                Item::Group(started) => {
+                    let fork = self.groups[started].fork;
+                    match self.forks[fork].keys.clone() {
+                        Some(keys) => {
+                            let mut object = serde_json::Map::new();
+                            for (b, key) in keys.into_iter().enumerate() {
+                                let found = self.values(started, b).into_iter().map(|(_, v)| v);
+                                let value = if self.forks[fork].singular[b] {
+                                    found.into_iter().next().unwrap_or(JSON::Null)
+                                } else {
+                                    JSON::Array(found.collect())
+                                };
+                                object.insert(key, value);
+                            }
+                            let path = std::mem::take(&mut self.groups[started].path);
+                            values.push((path, JSON::Object(object)));
+                        }
+                        None => {
+                            for b in 0..self.groups[started].lists.len() {
+                                values.extend(self.values(started, b));
+                            }
+                        }
+                    }
+                }
+            }
+        }
+        values
     }
 
+    /// Step into a child of the current scope; if we are capturing, this also writes its name to output.
+    fn enter(&mut self, found: QueryElement) {
+        if let (true, QueryElement::Field(name)) = (self.is_capturing(), &found) {
+            self.output.last_mut().unwrap().push_key(name.clone());
+        }
+        let later = self.can_match_after(&found);
+        let next = self.step(&found);
+        self.current_path.push(found);
+        let next = self.expand(next);
+        self.matching.push(next);
+        self.later.push(later);
+        self.seen.push(Vec::new());
+        if self.is_listing(self.current_path.len()) {
+            let path = self.current_path.clone();
+            if let Some(listing) = &mut self.listing {
+                listing.found.push((path, None));
+                listing.pending = Some(listing.found.len() - 1);
+            }
+        }
+    }
+    /// Would stepping into the child ``found`` lead to (or stay within) a match?
+    fn should_enter(&self, found: &QueryElement) -> bool {
+        self.is_capturing()
+            || self.is_listing(self.current_path.len() + 1)
+            || !self.step(found).is_empty()
+    }
     /// When we have recursive control over entering a scope or not, only enter if it advances our query match!
     fn enter_name(&mut self, name: &str) -> bool {
-        let continues_match = match self.next_step() {
-            NextStep::IsMatch(_) => {
-                // write this name to output.
-                self.output.last_mut().unwrap().push_key(name.to_owned());
-                true
-            }
-            NextStep::Found(QueryElement::Field(field)) => name == field,
-            _ => false,
-        };
+        let found = QueryElement::field(name);
+        let continues_match = self.should_enter(&found);
         if continues_match {
-            self.current_path.push(QueryElement::field(name));
+            self.enter(found);
         }
         continues_match
     }
     /// Sometimes we do not have control over entering a scope; so we just push without checking whether it advances our match or not.
     fn must_enter_name(&mut self, name: &str) {
-        self.current_path.push(QueryElement::field(name));
-        if self.is_match() {
-            // write this name to output.
-            self.output.last_mut().unwrap().push_key(name.to_owned());
+        self.enter(QueryElement::field(name));
+    }
+    /// A variant is the only key of the map around it, so nothing can come after it.
+    fn enter_variant(&mut self, variant: &str) {
+        self.must_enter_name(variant);
+        *self.later.last_mut().unwrap() = false;
+    }
+    /// Step back out of the child we're in; if the query names this field, remember that we saw it.
+    fn exit(&mut self) -> Option<QueryElement> {
+        let top = self.current_path.pop();
+        self.matching.pop();
+        self.later.pop();
+        self.seen.pop();
+        if let Some(found @ QueryElement::Field(name)) = &top {
+            // A JSON Pointer's ``/0`` names the key "0" too.
+            let names = |elem: &QueryElement| {
+                matches!(elem, QueryElement::Field(_) | QueryElement::IndexOrKey(_))
+                    && elem.matches(found, None)
+            };
+            let wanted = self.matching.last().unwrap().iter().any(|(node, _)| {
+                self.nodes[*node]
+                    .children
+                    .iter()
+                    .any(|child| self.nodes[*child].element.as_ref().is_some_and(names))
+            });
+            if wanted {
+                self.seen.last_mut().unwrap().push(name.clone());
+            }
         }
+        top
     }
     fn exit_name(&mut self, name: Option<&str>) {
-        let top = self.current_path.pop();
+        let top = self.exit();
         if let Some(name) = name {
             debug_assert_eq!(Some(QueryElement::field(name)), top);
         }
     }
+    /// Close the output frame of a sequence or map, if we opened one when we entered it.
+    fn exit_output_frame(&mut self) {
+        if self.is_capturing() {
+            // pop output stack and treat it as a value!
+            let mut top = self.output.pop().unwrap();
+            let slots = std::mem::take(&mut top.slots);
+            self.emit(top.finish(), slots);
+        }
+    }
     fn enter_sequence(&mut self, length: Option<usize>) {
-        if self.is_match() {
-            self.output.push(OutputStackFrame::list());
+        self.listed_type(JSONType::Array);
+        if self.is_match() || self.is_capturing() {
+            let slots = self.new_slots();
+            self.output
+                .push(OutputStackFrame::new(ElementKind::List, slots));
         }
-        self.state.push(State::Sequence(
-            0,
-            length.expect("All sequences have lengths?"),
-        ));
+        self.state.push(State::Sequence(0, length));
     }
     fn sequence_element<T: ?Sized>(&mut self, value: &T) -> Result<(), QueryExecErr>
     where
         T: serde::ser::Serialize,
     {
-        let index = match self.state.pop() {
-            Some(State::Sequence(idx, len)) => {
-                assert!(idx < len);
+        let index = match self.state.last() {
+            Some(&State::Sequence(idx, Some(len))) if idx >= len => {
+                return Err(self.fail(QueryExecErr::WrongLength {
+                    expected: len,
+                    found: idx + 1,
+                }))
+            }
+            Some(&State::Sequence(idx, len)) => {
+                self.state.pop();
                 self.state.push(State::Sequence(idx + 1, len));
                 idx
             }
-            x => panic!(
-                "state should be sequence but was {:?}; path={:?}",
-                x, self.current_path
-            ),
+            x => {
+                let msg = format!(
+                    "state should be sequence but was {:?}; path={:?}",
+                    x, self.current_path
+                );
+                return Err(self.fail(QueryExecErr::InternalError(msg)));
+            }
         };
-        if self.enter_index(index) {
-            value.serialize(&mut *self)?;
+        self.test_filters(value)?;
+        let should_enter = self.enter_index(index);
+        self.accepted.clear();
+        if should_enter {
+            self.visit(value)?;
             self.exit_index(index);
         }
+        self.stop_if_done(&QueryElement::array_item(index))
+    }
+    /// Is a filter waiting to test the children of the current scope?
+    fn has_filters(&self) -> bool {
+        self.matching.last().unwrap().iter().any(|(node, _)| {
+            self.nodes[*node]
+                .children
+                .iter()
+                .any(|child| matches!(self.nodes[*child].element, Some(QueryElement::Filter(_))))
+        })
+    }
+    /// Serde hands us the fields of a tuple or struct variant only after we have entered it, too late for a filter to test them.
+    /// So if a filter is waiting, collect the fields instead, and replay them from ``end_buffered_variant``.
+    fn buffer_variant(&mut self, variant: &str, fields: JSON) -> bool {
+        if !self.has_filters() {
+            return false;
+        }
+        self.state.push(State::Buffered(variant.to_owned(), fields));
+        true
+    }
+    /// Add a field to the variant we're buffering, if we are; ``key`` is None for a tuple variant.
+    fn buffered_field<T: ?Sized>(
+        &mut self,
+        key: Option<&str>,
+        value: &T,
+    ) -> Result<bool, QueryExecErr>
+    where
+        T: serde::ser::Serialize,
+    {
+        if !matches!(self.state.last(), Some(State::Buffered(..))) {
+            return Ok(false);
+        }
+        let value = serde_json::to_value(value)?;
+        match (self.state.last_mut(), key) {
+            (Some(State::Buffered(_, JSON::Array(items))), None) => items.push(value),
+            (Some(State::Buffered(_, JSON::Object(fields))), Some(key)) => {
+                fields.insert(key.to_owned(), value);
+            }
+            found => {
+                let msg = format!("Bad buffered variant state: {:?}", found);
+                return Err(self.fail(QueryExecErr::InternalError(msg)));
+            }
+        }
+        Ok(true)
+    }
+    /// Finish a variant we buffered, as if it were a newtype variant holding its fields.
+    fn end_buffered_variant(&mut self) -> Result<bool, QueryExecErr> {
+        if !matches!(self.state.last(), Some(State::Buffered(..))) {
+            return Ok(false);
+        }
+        if let Some(State::Buffered(variant, fields)) = self.state.pop() {
+            self.variant_value(&variant, &fields)?;
+        }
+        Ok(true)
+    }
+    /// Variants look like {variant: value} in JSON; we're inside that map, and ``value`` is all of its contents.
+    fn variant_value<T: ?Sized>(&mut self, variant: &str, value: &T) -> Result<(), QueryExecErr>
+    where
+        T: serde::ser::Serialize,
+    {
+        self.test_filters(value)?;
+        let should_enter = self.should_enter(&QueryElement::field(variant));
+        if should_enter {
+            self.enter_variant(variant);
+        }
+        self.accepted.clear();
+        if should_enter {
+            value.serialize(&mut *self)?;
+            self.exit_name(Some(variant));
+        }
+        self.exit_map()
+    }
+    /// Without a length, we cannot tell which element a negative index points at; say so rather than match nothing.
+    fn check_known_length(&mut self) -> Result<(), QueryExecErr> {
+        let counts_from_end = self.matching.last().unwrap().iter().any(|(node, _)| {
+            self.nodes[*node].children.iter().any(|child| {
+                self.nodes[*child]
+                    .element
+                    .as_ref()
+                    .is_some_and(QueryElement::counts_from_end)
+            })
+        });
+        if counts_from_end {
+            return Err(self.fail(QueryExecErr::Unsupported(format!(
+                "a negative index into a sequence of unknown length at {}",
+                JSONQuery::new(self.current_path.clone())
+            ))));
+        }
+        Ok(())
+    }
+    /// Buffer a sequence element or map value and run any filters waiting for it, so we know whether to enter it.
+    fn test_filters<T: ?Sized>(&mut self, value: &T) -> Result<(), QueryExecErr>
+    where
+        T: serde::ser::Serialize,
+    {
+        let mut candidate = None;
+        for &(node, group) in self.matching.last().unwrap() {
+            for &child in self.nodes[node].children.iter() {
+                if let Some(QueryElement::Filter(predicate)) = &self.nodes[child].element {
+                    if candidate.is_none() {
+                        candidate = Some(serde_json::to_value(value)?);
+                    }
+                    if predicate.test(candidate.as_ref().unwrap())? {
+                        self.accepted.push((child, group));
+                    }
+                }
+            }
+        }
         Ok(())
     }
     fn enter_index(&mut self, index: usize) -> bool {
-        let should_enter = match self.next_step() {
-            NextStep::NotMatching => false,
-            NextStep::Found(QueryElement::ArrayItem(x)) => (index == *x),
-            NextStep::Found(_) => false,
-            NextStep::IsMatch(_) => true,
-        };
+        let found = QueryElement::array_item(index);
+        let should_enter = self.should_enter(&found);
         if should_enter {
-            self.current_path.push(QueryElement::array_item(index));
+            self.enter(found);
         }
         should_enter
     }
     fn exit_index(&mut self, index: usize) {
-        let top = self.current_path.pop();
+        let top = self.exit();
         debug_assert_eq!(Some(QueryElement::array_item(index)), top);
     }
     fn exit_sequence(&mut self) -> Result<(), QueryExecErr> {
-        if self.is_match() {
-            // pop output stack and treat it as a value!
-            let top = self.output.pop().unwrap();
-            self.output.last_mut().unwrap().push(top);
-        }
+        self.exit_output_frame();
         let top = self.state.pop();
         match top {
-            Some(State::Sequence(pos, len)) => {
-                debug_assert_eq!(pos, len);
-                Ok(())
+            Some(State::Sequence(pos, Some(len))) if pos != len => {
+                Err(self.fail(QueryExecErr::WrongLength {
+                    expected: len,
+                    found: pos,
+                }))
             }
-            found => Err(QueryExecErr::InternalError(format!(
+            Some(State::Sequence(_, _)) => Ok(()),
+            found => Err(self.fail(QueryExecErr::InternalError(format!(
                 "Bad exit_sequence state: {:?}",
                 found
-            ))),
+            )))),
         }
     }
     fn enter_map(&mut self) {
-        if self.is_match() {
-            self.output.push(OutputStackFrame::map());
+        self.listed_type(JSONType::Object);
+        if self.is_match() || self.is_capturing() {
+            let slots = self.new_slots();
+            self.output
+                .push(OutputStackFrame::new(ElementKind::Map, slots));
         }
         self.state.push(State::StartMap);
     }
-    fn exit_map(&mut self) {
-        if self.is_match() && self.output.len() > 1 {
-            // pop output stack and treat it as a value!
-            let top = self.output.pop().unwrap();
-            self.output.last_mut().unwrap().push(top);
+    fn exit_map(&mut self) -> Result<(), QueryExecErr> {
+        self.exit_output_frame();
+        match self.state.pop() {
+            Some(State::StartMap) => Ok(()),
+            found => Err(self.fail(QueryExecErr::InternalError(format!(
+                "Bad exit_map state: {:?}",
+                found
+            )))),
         }
-        let top = self.state.pop();
-        debug_assert_eq!(top, Some(State::StartMap));
     }
     fn enter_map_key(&mut self) {
         self.state.push(State::MapKey);
     }
+    /// We always enter a map key, since we only find out whether we wanted it after serde hands it to us.
+    /// If a filter is waiting for it, we enter it with its value instead; see ``enter_map_value``.
+    fn map_key(&mut self, key: &str) {
+        if self.has_filters() {
+            self.state.push(State::PendingKey(key.to_string()));
+        } else {
+            self.state.push(State::MapKeyStr(key.to_string()));
+            self.must_enter_name(key);
+        }
+    }
     fn exit_map_key(&mut self) -> Result<(), QueryExecErr> {
         // Leave MapKeyStr on state stack!
         match self.state.last() {
-            Some(State::MapKeyStr(_)) => Ok(()),
-            _ => Err(QueryExecErr::InternalError(format!(
+            Some(State::MapKeyStr(_) | State::PendingKey(_)) => Ok(()),
+            _ => Err(self.fail(QueryExecErr::InternalError(format!(
                 "Map key not a simple String! {:?}",
                 self.current_path
-            ))),
+            )))),
         }
     }
-    fn enter_map_value(&mut self) {
+    /// Returns whether the value under this key could contribute to our query at all.
+    fn enter_map_value<T: ?Sized>(&mut self, value: &T) -> Result<bool, QueryExecErr>
+    where
+        T: serde::ser::Serialize,
+    {
         match self.state.last() {
             Some(State::MapKeyStr(_)) => {}
-            _ => panic!(
-                "enter_map_value {:?} state={:?}",
-                self.current_path, self.state
-            ),
+            Some(State::PendingKey(name)) => {
+                let name = name.clone();
+                self.state.pop();
+                self.test_filters(value)?;
+                self.state.push(State::MapKeyStr(name.clone()));
+                self.must_enter_name(&name);
+                self.accepted.clear();
+            }
+            _ => {
+                return Err(self.fail(QueryExecErr::InternalError(format!(
+                    "enter_map_value {:?} state={:?}",
+                    self.current_path, self.state
+                ))))
+            }
         };
         self.state.push(State::MapValue);
+        Ok(self.is_capturing()
+            || self.is_listing(self.current_path.len())
+            || !self.matching.last().unwrap().is_empty())
     }
     fn exit_map_value(&mut self) -> Result<(), QueryExecErr> {
         match self.state.pop() {
             Some(State::MapValue) => {}
             actual => {
-                return Err(QueryExecErr::InternalError(format!(
+                return Err(self.fail(QueryExecErr::InternalError(format!(
                     "Expected MapValue state, found: {:?}",
                     actual
-                )))
+                ))))
             }
         }
-        match self.state.pop() {
+        let name = match self.state.pop() {
             Some(State::MapKeyStr(name)) => {
                 self.exit_name(Some(&name));
+                name
             }
             actual => {
-                return Err(QueryExecErr::InternalError(format!(
+                return Err(self.fail(QueryExecErr::InternalError(format!(
                     "Expected MapKeyStr state, found: {:?}",
                     actual
-                )))
+                ))))
             }
-        }
+        };
         match self.state.pop() {
-            Some(State::MapKey) => Ok(()),
-            actual => Err(QueryExecErr::InternalError(format!(
+            Some(State::MapKey) => self.stop_if_done(&QueryElement::Field(name)),
+            actual => Err(self.fail(QueryExecErr::InternalError(format!(
                 "Expected MapKey state, found: {:?}",
                 actual
-            ))),
+            )))),
         }
     }
 }
 
 /// An enum representing a runtime error given a correctly-parsed query.
-#[derive(Serialize, Deserialize, Debug, PartialEq, Eq)]
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
 pub enum QueryExecErr {
     /// You gave us a query that has no fields or array accesses in it.
     /// Just call serde_json::to_value instead of going through the query API!
@@ -343,6 +1035,16 @@ pub enum QueryExecErr {
     InternalError(String),
     /// Since we're currently implementing a serde Serializer to run the queries, we need a catch-all for custom errors, e.g., in user-specified serialization targets.
     Serialization(String),
+    /// The result of this query could not be deserialized into the type you asked for; String is the serde_json error.
+    Deserialization(JSONQuery, String),
+    /// A sequence promised serde one length but gave us a different number of elements.
+    WrongLength { expected: usize, found: usize },
+    /// A map key that JSON cannot represent, like a sequence or a map; String is the path to that map.
+    BadMapKey(String),
+    /// A ``QueryPolicy`` does not allow this path to be read.
+    Forbidden(JSONQuery),
+    /// Something the query asks for that we cannot do with this data; String says what it was.
+    Unsupported(String),
 }
 
 impl From<serde_json::Error> for QueryExecErr {
@@ -430,21 +1132,37 @@ impl<'a> serde::Serializer for &'a mut QueryExecutor {
     fn serialize_str(self, v: &str) -> Result<Self::Ok, Self::Error> {
         match self.state.last() {
             Some(State::MapKey) => {
-                self.state.push(State::MapKeyStr(v.to_string()));
-                self.must_enter_name(v);
+                self.map_key(v);
                 Ok(())
             }
-            Some(State::MapKeyStr(_)) => Err(QueryExecErr::InternalError(
-                "Shouldn't see multiple str for the same key!".into(),
-            )),
-            Some(_) => self.possible_result(&v),
-            Option::None => Err(QueryExecErr::InternalError(
-                "&str value with no state!".into(),
+            Some(State::MapKeyStr(_) | State::PendingKey(_)) => Err(self.fail(
+                QueryExecErr::InternalError("Shouldn't see multiple str for the same key!".into()),
             )),
+            // A string at the root, e.g., a candidate that a filter tests with ``@``.
+            _ => self.possible_result(&v),
         }
     }
-    fn serialize_bytes(self, _v: &[u8]) -> Result<Self::Ok, Self::Error> {
-        unimplemented!()
+    fn serialize_bytes(self, v: &[u8]) -> Result<Self::Ok, Self::Error> {
+        let text = match self.options.bytes.encode(v) {
+            Some(text) => text,
+            None => {
+                self.enter_sequence(Some(v.len()));
+                for byte in v {
+                    self.sequence_element(byte)?;
+                }
+                return self.exit_sequence();
+            }
+        };
+        // As a string, the bytes are a single value, but a query can still look inside them.
+        self.possible_result(&text)?;
+        if !self.is_capturing() && !self.matching.last().unwrap().is_empty() {
+            self.state.push(State::Sequence(0, Some(v.len())));
+            for byte in v {
+                self.sequence_element(byte)?;
+            }
+            self.state.pop();
+        }
+        Ok(())
     }
     fn serialize_none(self) -> Result<Self::Ok, Self::Error> {
         self.serialize_unit()
@@ -497,13 +1215,15 @@ impl<'a> serde::Serializer for &'a mut QueryExecutor {
     where
         T: serde::Serialize,
     {
-        if self.enter_name(variant) {
-            value.serialize(&mut *self)?;
-            self.exit_name(Some(variant));
-        }
-        Ok(())
+        self.check_not_key()?;
+        self.enter_map();
+        self.variant_value(variant, value)
     }
     fn serialize_seq(self, len: Option<usize>) -> Result<Self::SerializeSeq, Self::Error> {
+        self.check_not_key()?;
+        if len.is_none() {
+            self.check_known_length()?;
+        }
         self.enter_sequence(len);
         Ok(self)
     }
@@ -525,12 +1245,16 @@ impl<'a> serde::Serializer for &'a mut QueryExecutor {
         variant: &'static str,
         len: usize,
     ) -> Result<Self::SerializeTupleVariant, Self::Error> {
+        self.check_not_key()?;
         self.enter_map();
-        self.must_enter_name(variant);
-        self.enter_sequence(Some(len));
+        if !self.buffer_variant(variant, JSON::Array(Vec::with_capacity(len))) {
+            self.enter_variant(variant);
+            self.enter_sequence(Some(len));
+        }
         Ok(self)
     }
     fn serialize_map(self, _len: Option<usize>) -> Result<Self::SerializeMap, Self::Error> {
+        self.check_not_key()?;
         self.enter_map();
         Ok(self)
     }
@@ -548,8 +1272,13 @@ impl<'a> serde::Serializer for &'a mut QueryExecutor {
         variant: &'static str,
         _len: usize,
     ) -> Result<Self::SerializeStructVariant, Self::Error> {
+        // Struct variants look like {variant: {fields...}} in JSON.
+        self.check_not_key()?;
         self.enter_map();
-        self.must_enter_name(variant);
+        if !self.buffer_variant(variant, JSON::Object(serde_json::Map::new())) {
+            self.enter_variant(variant);
+            self.enter_map();
+        }
         Ok(self)
     }
 }
@@ -587,13 +1316,13 @@ impl<'a> serde::ser::SerializeMap for &'a mut QueryExecutor {
     where
         T: serde::Serialize,
     {
-        self.enter_map_value();
-        value.serialize(&mut **self)?;
+        if self.enter_map_value(value)? {
+            self.visit(value)?;
+        }
         self.exit_map_value()
     }
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        self.exit_map();
-        Ok(())
+        self.exit_map()
     }
 }
 
@@ -630,13 +1359,18 @@ impl<'a> serde::ser::SerializeTupleVariant for &'a mut QueryExecutor {
     where
         T: serde::Serialize,
     {
+        if self.buffered_field(None, value)? {
+            return Ok(());
+        }
         self.sequence_element(value)
     }
     fn end(self) -> Result<Self::Ok, Self::Error> {
+        if self.end_buffered_variant()? {
+            return Ok(());
+        }
         self.exit_sequence()?;
-        self.exit_map();
         self.exit_name(None);
-        Ok(())
+        self.exit_map()
     }
 }
 impl<'a> serde::ser::SerializeStruct for &'a mut QueryExecutor {
@@ -650,15 +1384,17 @@ impl<'a> serde::ser::SerializeStruct for &'a mut QueryExecutor {
     where
         T: serde::Serialize,
     {
-        if self.enter_name(key) {
-            value.serialize(&mut **self)?;
+        self.test_filters(value)?;
+        let should_enter = self.enter_name(key);
+        self.accepted.clear();
+        if should_enter {
+            self.visit(value)?;
             self.exit_name(Some(key));
         }
-        Ok(())
+        self.stop_if_done(&QueryElement::field(key))
     }
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        self.exit_map();
-        Ok(())
+        self.exit_map()
     }
 }
 
@@ -673,15 +1409,24 @@ impl<'a> serde::ser::SerializeStructVariant for &'a mut QueryExecutor {
     where
         T: serde::Serialize,
     {
-        if self.enter_name(key) {
-            value.serialize(&mut **self)?;
+        if self.buffered_field(Some(key), value)? {
+            return Ok(());
+        }
+        self.test_filters(value)?;
+        let should_enter = self.enter_name(key);
+        self.accepted.clear();
+        if should_enter {
+            self.visit(value)?;
             self.exit_name(Some(key));
         }
-        Ok(())
+        self.stop_if_done(&QueryElement::field(key))
     }
     fn end(self) -> Result<Self::Ok, Self::Error> {
-        self.exit_map();
+        if self.end_buffered_variant()? {
+            return Ok(());
+        }
+        self.exit_map()?;
         self.exit_name(None);
-        Ok(())
+        self.exit_map()
     }
 }
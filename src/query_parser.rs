@@ -1,10 +1,54 @@
-use crate::query::QueryElement;
+use crate::filter::{Comparison, Operand, Predicate};
+use crate::query::{is_bare_key, JSONQuery, QueryElement};
 
 pub(crate) fn parse_query(input: &str) -> Result<Vec<QueryElement>, QueryParseErr> {
-    let mut output = Vec::new();
     let mut parser = Parser::from(input);
-    while let Some(it) = parser.next()? {
-        output.push(it);
+    let mut branches = parser.read_branches()?;
+    if parser.peek().is_some() {
+        return Err(QueryParseErr::BadCharacter(parser.position));
+    }
+    // A top-level ``.a,.b`` is a union without its parentheses.
+    if branches.len() == 1 {
+        Ok(branches.remove(0).elements)
+    } else {
+        Ok(vec![QueryElement::Union(branches)])
+    }
+}
+
+/// Split an RFC 6901 JSON Pointer like ``/items/0/name`` into query elements.
+pub(crate) fn parse_json_pointer(input: &str) -> Result<Vec<QueryElement>, QueryParseErr> {
+    let mut output = Vec::new();
+    if input.is_empty() {
+        return Ok(output);
+    } else if !input.starts_with('/') {
+        return Err(QueryParseErr::BadCharacter(0));
+    }
+    // Track character positions for errors, like the main parser does.
+    let mut position = 1;
+    for token in input[1..].split('/') {
+        let mut name = String::new();
+        let mut chars = token.chars().enumerate();
+        while let Some((i, ch)) = chars.next() {
+            if ch == '~' {
+                match chars.next() {
+                    Some((_, '0')) => name.push('~'),
+                    Some((_, '1')) => name.push('/'),
+                    _ => return Err(QueryParseErr::BadEscape(position + i)),
+                }
+            } else {
+                name.push(ch);
+            }
+        }
+        position += token.chars().count() + 1;
+        // Array indices are "0" or digits without a leading zero.
+        let is_index = name == "0"
+            || (!name.is_empty()
+                && !name.starts_with('0')
+                && name.chars().all(|ch| ch.is_ascii_digit()));
+        output.push(match name.parse::<usize>() {
+            Ok(index) if is_index => QueryElement::IndexOrKey(index),
+            _ => QueryElement::Field(name),
+        });
     }
     Ok(output)
 }
@@ -16,8 +60,8 @@ pub(crate) fn parse_query(input: &str) -> Result<Vec<QueryElement>, QueryParseEr
 pub enum QueryParseErr {
     /// Each parsable element must start with '.' or '['
     BadCharacter(usize),
-    /// Need a field name; encountered a ".." in the query.
-    MissingField,
+    /// Need a field name here; encountered a "..." or a trailing "." in the query.
+    MissingField(usize),
     /// Need a number; encountered a "[]" in the query.
     MissingNumber(usize),
     /// Got some kind of non-decimal digit inside the brackets "[]".
@@ -30,6 +74,37 @@ pub enum QueryParseErr {
     Unexpected(usize, char),
     /// Could not parse the number in your brackets to a usize. String is the IntError in question.
     BadIndex(usize, String),
+    /// A slice "[start:end:step]" needs a positive step; we visit elements in order, so we cannot walk backwards.
+    BadSlice(usize),
+    /// Could not understand the filter "[?(...)]" at this position; expected a path, a JSON literal, or a comparison.
+    BadFilter(usize),
+    /// A quoted string starting here has a bad escape sequence.
+    BadString(usize),
+    /// Valid JSONPath that we cannot execute, e.g., "&&" inside a filter. String says what it was.
+    Unsupported(usize, String),
+    /// A JSON Pointer may only use the escapes "~0" and "~1".
+    BadEscape(usize),
+}
+
+impl QueryParseErr {
+    /// The character index where the parser detected the failure, if the error has one, e.g., to underline it.
+    pub fn position(&self) -> Option<usize> {
+        match self {
+            QueryParseErr::BadCharacter(at)
+            | QueryParseErr::MissingField(at)
+            | QueryParseErr::MissingNumber(at)
+            | QueryParseErr::BadArray(at)
+            | QueryParseErr::BadField(at)
+            | QueryParseErr::Unexpected(at, _)
+            | QueryParseErr::BadIndex(at, _)
+            | QueryParseErr::BadSlice(at)
+            | QueryParseErr::BadFilter(at)
+            | QueryParseErr::BadString(at)
+            | QueryParseErr::Unsupported(at, _)
+            | QueryParseErr::BadEscape(at) => Some(*at),
+            QueryParseErr::UnexpectedEOF(_) => None,
+        }
+    }
 }
 
 impl std::fmt::Display for QueryParseErr {
@@ -40,9 +115,13 @@ impl std::fmt::Display for QueryParseErr {
 
 impl std::error::Error for QueryParseErr {}
 
-struct Parser {
+pub(crate) struct Parser {
     data: Vec<char>,
-    position: usize,
+    pub(crate) position: usize,
+    /// How many filters "[?(...)]" we are inside; paths in filters end at whitespace, ')' or an operator.
+    filter_depth: usize,
+    /// How many unions "(...)" or objects "{...}" we are inside; paths in them also end at whitespace.
+    group_depth: usize,
 }
 
 impl From<&str> for Parser {
@@ -50,20 +129,44 @@ impl From<&str> for Parser {
         Parser {
             data: input.chars().collect(),
             position: 0,
+            filter_depth: 0,
+            group_depth: 0,
         }
     }
 }
 
+/// Characters that can end a relative path inside of a filter.
+fn ends_filter_path(ch: char) -> bool {
+    ch.is_whitespace() || ch == ')' || ch == '=' || ch == '!' || ch == '<' || ch == '>'
+}
+
 impl Parser {
-    fn peek(&self) -> Option<char> {
+    pub(crate) fn peek(&self) -> Option<char> {
         self.data.get(self.position).cloned()
     }
-    fn advance(&mut self) -> Option<char> {
+    pub(crate) fn peek_ahead(&self, offset: usize) -> Option<char> {
+        self.data.get(self.position + offset).cloned()
+    }
+    pub(crate) fn advance(&mut self) -> Option<char> {
         let found = self.peek();
         self.position += 1;
         found
     }
-    fn consume(&mut self, expected: char) -> Result<(), QueryParseErr> {
+    pub(crate) fn skip_whitespace(&mut self) {
+        while self.peek().is_some_and(char::is_whitespace) {
+            self.position += 1;
+        }
+    }
+    /// Does the current path end here, because we're at the end of a union branch or of a path inside a filter?
+    fn at_path_end(&self) -> bool {
+        match self.peek() {
+            None => true,
+            Some(',') | Some(')') | Some('}') => true,
+            Some(ch) if ch.is_whitespace() && self.group_depth > 0 => true,
+            Some(ch) => self.filter_depth > 0 && ends_filter_path(ch),
+        }
+    }
+    pub(crate) fn consume(&mut self, expected: char) -> Result<(), QueryParseErr> {
         match self.advance() {
             None => Err(QueryParseErr::UnexpectedEOF(expected)),
             Some(actual) => {
@@ -77,33 +180,89 @@ impl Parser {
     }
     fn read_array(&mut self) -> Result<QueryElement, QueryParseErr> {
         self.consume('[')?;
-        let mut digits = String::new();
+        // Up to three ':'-separated numbers; more than one means we have a slice.
+        let mut parts = vec![String::new()];
         let start = self.position;
 
+        if self.peek() == Some('*') {
+            self.consume('*')?;
+            self.consume(']')?;
+            return Ok(QueryElement::AnyItem);
+        } else if self.peek() == Some('?') {
+            return self.read_filter();
+        } else if self.peek() == Some('"') {
+            let field = self.read_string()?;
+            self.consume(']')?;
+            return Ok(QueryElement::Field(field));
+        }
+
+        let mut closed = false;
         while let Some(ch) = self.advance() {
+            let part = parts.last_mut().unwrap();
             if ch == ']' {
+                closed = true;
                 break;
-            } else if ch.is_digit(10) {
-                digits.push(ch);
+            } else if ch.is_ascii_digit() || (ch == '-' && part.is_empty()) {
+                part.push(ch);
+            } else if ch == ':' && parts.len() < 3 {
+                parts.push(String::new());
             } else {
                 return Err(QueryParseErr::BadArray(self.position - 1));
             }
         }
 
-        if digits.is_empty() {
+        if parts.len() == 1 && parts[0].is_empty() {
             Err(QueryParseErr::MissingNumber(start))
-        } else {
+        } else if !closed {
+            Err(QueryParseErr::UnexpectedEOF(']'))
+        } else if parts.len() == 1 {
+            let (negative, digits) = match parts[0].strip_prefix('-') {
+                Some(digits) => (true, digits),
+                None => (false, parts[0].as_str()),
+            };
             let num = digits
                 .parse::<usize>()
                 .map_err(|e| QueryParseErr::BadIndex(start, e.to_string()))?;
-            Ok(QueryElement::ArrayItem(num))
+            if negative && num > 0 {
+                Ok(QueryElement::ArrayItemFromEnd(num))
+            } else {
+                Ok(QueryElement::ArrayItem(num))
+            }
+        } else {
+            let mut bounds = Vec::new();
+            for part in parts.iter() {
+                bounds.push(if part.is_empty() {
+                    None
+                } else {
+                    Some(
+                        part.parse::<isize>()
+                            .map_err(|e| QueryParseErr::BadIndex(start, e.to_string()))?,
+                    )
+                });
+            }
+            let step = match bounds.get(2) {
+                Some(Some(step)) if *step > 0 => *step as usize,
+                Some(Some(_)) => return Err(QueryParseErr::BadSlice(start)),
+                Some(None) | None => 1,
+            };
+            Ok(QueryElement::slice(bounds[0], bounds[1], step))
         }
     }
     fn read_field(&mut self) -> Result<QueryElement, QueryParseErr> {
         self.consume('.')?;
+        if self.peek() == Some('"') {
+            return Ok(QueryElement::Field(self.read_string()?));
+        } else if self.peek() == Some('*') {
+            self.consume('*')?;
+            return match self.peek() {
+                Some('.') | Some('[') | Some('(') | Some('{') => Ok(QueryElement::AnyField),
+                _ if self.at_path_end() => Ok(QueryElement::AnyField),
+                _ => Err(QueryParseErr::BadField(self.position)),
+            };
+        }
         let mut id = String::new();
         while let Some(ch) = self.peek() {
-            if ch == '.' || ch == '[' {
+            if ch == '.' || ch == '[' || ch == '(' || ch == '{' || self.at_path_end() {
                 break;
             } else if ch.is_whitespace() {
                 return Err(QueryParseErr::BadField(self.position - 1));
@@ -112,15 +271,198 @@ impl Parser {
             id.push(ch);
         }
         if id.is_empty() {
-            Err(QueryParseErr::MissingField)
+            Err(QueryParseErr::MissingField(self.position))
         } else {
             Ok(QueryElement::Field(id))
         }
     }
+    /// A ".." is followed by a field (``..name``, ``..*``), an array access (``..[0]``) or a group (``..(.a,.b)``).
+    fn read_recursive_descent(&mut self) -> Result<QueryElement, QueryParseErr> {
+        self.consume('.')?;
+        match self.peek_ahead(1) {
+            Some('[') | Some('(') | Some('{') => self.consume('.')?,
+            Some('.') => return Err(QueryParseErr::MissingField(self.position + 1)),
+            _ => {}
+        }
+        Ok(QueryElement::RecursiveDescent)
+    }
+    /// Read elements until the path ends; ``@`` may start a path, so the empty path can be written.
+    fn read_path(&mut self) -> Result<JSONQuery, QueryParseErr> {
+        if self.peek() == Some('@') {
+            self.consume('@')?;
+        }
+        let mut elements = Vec::new();
+        while let Some(it) = self.next()? {
+            elements.push(it);
+        }
+        Ok(JSONQuery::new(elements))
+    }
+    /// One or more paths separated by commas: ``.a, .b[0]``
+    fn read_branches(&mut self) -> Result<Vec<JSONQuery>, QueryParseErr> {
+        let mut branches = vec![self.read_path()?];
+        self.skip_whitespace();
+        while self.peek() == Some(',') {
+            self.consume(',')?;
+            self.skip_whitespace();
+            branches.push(self.read_path()?);
+            self.skip_whitespace();
+        }
+        Ok(branches)
+    }
+    /// Parse a union like ``(.a, .b)``.
+    fn read_union(&mut self) -> Result<QueryElement, QueryParseErr> {
+        self.consume('(')?;
+        self.group_depth += 1;
+        self.skip_whitespace();
+        let branches = self.read_branches()?;
+        self.skip_whitespace();
+        self.consume(')')?;
+        self.group_depth -= 1;
+        Ok(QueryElement::Union(branches))
+    }
+    /// Parse an object construction like ``{name: .a, "the age": .b}``.
+    fn read_object(&mut self) -> Result<QueryElement, QueryParseErr> {
+        self.consume('{')?;
+        self.group_depth += 1;
+        self.skip_whitespace();
+        let mut entries = Vec::new();
+        while self.peek() != Some('}') {
+            let key = if self.peek() == Some('"') {
+                self.read_string()?
+            } else {
+                let mut key = String::new();
+                while let Some(ch) = self.peek().filter(|ch| is_bare_key(*ch)) {
+                    self.consume(ch)?;
+                    key.push(ch);
+                }
+                if key.is_empty() {
+                    return Err(QueryParseErr::BadField(self.position));
+                }
+                key
+            };
+            self.skip_whitespace();
+            self.consume(':')?;
+            self.skip_whitespace();
+            entries.push((key, self.read_path()?));
+            self.skip_whitespace();
+            if self.peek() != Some(',') {
+                break;
+            }
+            self.consume(',')?;
+            self.skip_whitespace();
+        }
+        self.consume('}')?;
+        self.group_depth -= 1;
+        Ok(QueryElement::Object(entries))
+    }
+    /// Parse a filter like ``[?(.state == "failed")]``; the '[' is already consumed.
+    fn read_filter(&mut self) -> Result<QueryElement, QueryParseErr> {
+        let start = self.position;
+        self.consume('?')?;
+        self.consume('(')?;
+        self.skip_whitespace();
+        let lhs = self.read_operand()?;
+        self.skip_whitespace();
+        let predicate = if self.peek() == Some(')') {
+            match lhs {
+                Operand::Path(path) => Predicate::Exists(path),
+                Operand::Literal(_) => return Err(QueryParseErr::BadFilter(start)),
+            }
+        } else {
+            let op = self.read_comparison()?;
+            self.skip_whitespace();
+            let rhs = self.read_operand()?;
+            self.skip_whitespace();
+            Predicate::Compare(lhs, op, rhs)
+        };
+        self.consume(')')?;
+        self.consume(']')?;
+        Ok(QueryElement::Filter(predicate))
+    }
+    fn read_comparison(&mut self) -> Result<Comparison, QueryParseErr> {
+        let start = self.position;
+        let op = match (self.advance(), self.peek()) {
+            (Some('='), Some('=')) => Comparison::Eq,
+            (Some('!'), Some('=')) => Comparison::Ne,
+            (Some('<'), Some('=')) => Comparison::Le,
+            (Some('>'), Some('=')) => Comparison::Ge,
+            (Some('<'), _) => return Ok(Comparison::Lt),
+            (Some('>'), _) => return Ok(Comparison::Gt),
+            _ => return Err(QueryParseErr::BadFilter(start)),
+        };
+        self.advance();
+        Ok(op)
+    }
+    /// Either a relative path (``@``, ``.field``, ``[0]``) or a JSON literal.
+    fn read_operand(&mut self) -> Result<Operand, QueryParseErr> {
+        let start = self.position;
+        match self.peek() {
+            Some('@') | Some('.') | Some('[') => {
+                self.filter_depth += 1;
+                let path = self.read_path()?;
+                self.filter_depth -= 1;
+                Ok(Operand::Path(path))
+            }
+            Some('"') => Ok(Operand::Literal(self.read_string()?.into())),
+            Some(ch) if ch == '-' || ch.is_ascii_alphanumeric() => {
+                while self
+                    .peek()
+                    .is_some_and(|ch| ch.is_ascii_alphanumeric() || "+-.".contains(ch))
+                {
+                    self.position += 1;
+                }
+                self.read_literal(start)
+            }
+            _ => Err(QueryParseErr::BadFilter(start)),
+        }
+    }
+    /// A double-quoted string with JSON escapes, e.g., ``"a.b"`` or ``"tab\\t"``.
+    pub(crate) fn read_string(&mut self) -> Result<String, QueryParseErr> {
+        self.read_quoted('"')
+    }
+    /// A string in single or double quotes with JSON escapes; ``\\'`` is allowed too, like JSONPath does.
+    pub(crate) fn read_quoted(&mut self, quote: char) -> Result<String, QueryParseErr> {
+        let start = self.position;
+        self.consume(quote)?;
+        // Rewrite as a double-quoted JSON string, so serde_json can handle the escapes.
+        let mut text = String::from('"');
+        let mut escaped = false;
+        loop {
+            match self.advance() {
+                None => return Err(QueryParseErr::UnexpectedEOF(quote)),
+                Some(ch) if ch == quote && !escaped => break,
+                Some('\'') if escaped => {
+                    text.pop();
+                    text.push('\'');
+                }
+                Some('"') if !escaped => text.push_str("\\\""),
+                Some(ch) => text.push(ch),
+            }
+            escaped = !escaped && text.ends_with('\\');
+        }
+        text.push('"');
+        serde_json::from_str(&text).map_err(|_| QueryParseErr::BadString(start))
+    }
+    /// Hand everything we skipped over since ``start`` to serde_json.
+    pub(crate) fn read_literal(&mut self, start: usize) -> Result<Operand, QueryParseErr> {
+        let text: String = self.data[start..self.position].iter().collect();
+        serde_json::from_str(&text)
+            .map(Operand::Literal)
+            .map_err(|_| QueryParseErr::BadFilter(start))
+    }
     fn next(&mut self) -> Result<Option<QueryElement>, QueryParseErr> {
+        if self.at_path_end() {
+            return Ok(None);
+        }
         if let Some(ch) = self.peek() {
             Ok(Some(if ch == '[' {
                 self.read_array()?
+            } else if ch == '(' {
+                self.read_union()?
+            } else if ch == '{' {
+                self.read_object()?
+            } else if ch == '.' && self.peek_ahead(1) == Some('.') {
+                self.read_recursive_descent()?
             } else if ch == '.' {
                 self.read_field()?
             } else {
@@ -137,6 +479,7 @@ mod tests {
     use super::parse_query;
     use super::QueryParseErr;
     use crate::query::QueryElement as Q;
+    use crate::JSONQuery;
 
     #[test]
     fn test_dots_happy() {
@@ -169,11 +512,302 @@ mod tests {
         )
     }
 
+    #[test]
+    fn test_wildcards() {
+        assert_eq!(
+            parse_query(".a.*[*].b").unwrap(),
+            vec![Q::field("a"), Q::any_field(), Q::any_item(), Q::field("b")]
+        );
+        assert_eq!(parse_query(".*x").unwrap_err(), QueryParseErr::BadField(2));
+        assert_eq!(
+            parse_query("[*x]").unwrap_err(),
+            QueryParseErr::Unexpected(2, ']')
+        );
+    }
+
+    #[test]
+    fn test_recursive_descent() {
+        assert_eq!(
+            parse_query("..a.b").unwrap(),
+            vec![Q::recursive_descent(), Q::field("a"), Q::field("b")]
+        );
+        assert_eq!(
+            parse_query(".a..[3]..*").unwrap(),
+            vec![
+                Q::field("a"),
+                Q::recursive_descent(),
+                Q::array_item(3),
+                Q::recursive_descent(),
+                Q::any_field()
+            ]
+        );
+        for query in &["..a.b", ".a..[3]..*", "..[*]"] {
+            assert_eq!(*query, JSONQuery::parse(query).unwrap().to_string());
+        }
+        assert_eq!(
+            parse_query("...a").unwrap_err(),
+            QueryParseErr::MissingField(2)
+        );
+        assert_eq!(
+            parse_query(".a..").unwrap_err(),
+            QueryParseErr::MissingField(4)
+        );
+    }
+
+    #[test]
+    fn test_negative_index() {
+        assert_eq!(
+            parse_query("[-1][-0][-12]").unwrap(),
+            vec![
+                Q::array_item_from_end(1),
+                Q::array_item(0),
+                Q::array_item_from_end(12)
+            ]
+        );
+        assert_eq!("[-3]", JSONQuery::parse("[-3]").unwrap().to_string());
+        let zero = JSONQuery::single(Q::array_item_from_end(0));
+        assert_eq!("[0]", zero.to_string());
+        assert_eq!(zero, JSONQuery::parse(&zero.to_string()).unwrap());
+        assert_eq!(parse_query("[1-]").unwrap_err(), QueryParseErr::BadArray(2));
+        assert_eq!(
+            parse_query("[-]").unwrap_err(),
+            QueryParseErr::BadIndex(1, "cannot parse integer from empty string".into())
+        );
+    }
+
+    #[test]
+    fn test_slices() {
+        assert_eq!(
+            parse_query("[1:3][:-2][::2][-1:]").unwrap(),
+            vec![
+                Q::slice(Some(1), Some(3), 1),
+                Q::slice(None, Some(-2), 1),
+                Q::slice(None, None, 2),
+                Q::slice(Some(-1), None, 1),
+            ]
+        );
+        for query in &["[1:3]", "[:-2]", "[::2]", "[-1:]", "[:]"] {
+            assert_eq!(*query, JSONQuery::parse(query).unwrap().to_string());
+        }
+        assert_eq!(
+            parse_query("[::0]").unwrap_err(),
+            QueryParseErr::BadSlice(1)
+        );
+        assert_eq!(
+            parse_query("[::-1]").unwrap_err(),
+            QueryParseErr::BadSlice(1)
+        );
+        assert_eq!(
+            parse_query("[1:2:3:4]").unwrap_err(),
+            QueryParseErr::BadArray(6)
+        );
+        assert_eq!(
+            parse_query("[1:2").unwrap_err(),
+            QueryParseErr::UnexpectedEOF(']')
+        );
+    }
+
+    #[test]
+    fn test_filters() {
+        use crate::filter::{Comparison, Operand, Predicate};
+        assert_eq!(
+            parse_query(".jobs[?(.state == \"failed\")].id").unwrap(),
+            vec![
+                Q::field("jobs"),
+                Q::filter(Predicate::Compare(
+                    Operand::Path(JSONQuery::parse(".state").unwrap()),
+                    Comparison::Eq,
+                    Operand::Literal("failed".into())
+                )),
+                Q::field("id")
+            ]
+        );
+        assert_eq!(
+            parse_query("[?(@[0]>=-2.5e1)]").unwrap(),
+            vec![Q::filter(Predicate::Compare(
+                Operand::Path(JSONQuery::parse("[0]").unwrap()),
+                Comparison::Ge,
+                Operand::Literal(serde_json::json!(-25.0))
+            ))]
+        );
+        for query in &[
+            "[?(.a)]",
+            "[?(@ != null)]",
+            "[?(.a.b < .c[1])]",
+            "[?(.tags[?(@ == \"x\")])].name",
+            "[?(.name > \"a \\\"quoted\\\" [string])\")]",
+        ] {
+            assert_eq!(*query, JSONQuery::parse(query).unwrap().to_string());
+        }
+        assert_eq!(
+            parse_query("[?(3)]").unwrap_err(),
+            QueryParseErr::BadFilter(1)
+        );
+        assert_eq!(
+            parse_query("[?(.a = 3)]").unwrap_err(),
+            QueryParseErr::BadFilter(6)
+        );
+        assert_eq!(
+            parse_query("[?(.a == x)]").unwrap_err(),
+            QueryParseErr::BadFilter(9)
+        );
+        assert_eq!(
+            parse_query("[?(.a == 3").unwrap_err(),
+            QueryParseErr::UnexpectedEOF(')')
+        );
+    }
+
+    #[test]
+    fn test_quoted_fields() {
+        assert_eq!(
+            parse_query(r#"."a.b"["my key"][0]."x[\"]\u0041""#).unwrap(),
+            vec![
+                Q::field("a.b"),
+                Q::field("my key"),
+                Q::array_item(0),
+                Q::field("x[\"]A")
+            ]
+        );
+        assert_eq!(
+            parse_query(r#"..["*"]"#).unwrap(),
+            vec![Q::recursive_descent(), Q::field("*")]
+        );
+        for (query, printed) in &[
+            (r#"["a.b"]"#, r#"."a.b""#),
+            (r#"."plain""#, ".plain"),
+            (r#".."my key""#, r#".."my key""#),
+            (r#".""[0]"#, r#".""[0]"#),
+            (r#"[?(."a b" == 1)]"#, r#"[?(."a b" == 1)]"#),
+        ] {
+            let parsed = JSONQuery::parse(query).unwrap();
+            assert_eq!(*printed, parsed.to_string());
+            assert_eq!(parsed, JSONQuery::parse(printed).unwrap());
+        }
+        assert_eq!(
+            parse_query(r#"."a\qb""#).unwrap_err(),
+            QueryParseErr::BadString(1)
+        );
+        assert_eq!(
+            parse_query(r#"["a""#).unwrap_err(),
+            QueryParseErr::UnexpectedEOF(']')
+        );
+        assert_eq!(
+            parse_query(r#"."a"#).unwrap_err(),
+            QueryParseErr::UnexpectedEOF('"')
+        );
+    }
+
+    #[test]
+    fn test_unions_and_objects() {
+        let branch = |q: &str| JSONQuery::parse(q).unwrap();
+        assert_eq!(
+            parse_query(".a,.b[0]").unwrap(),
+            vec![Q::union(vec![branch(".a"), branch(".b[0]")])]
+        );
+        assert_eq!(
+            parse_query(".a( .b, [*] ).c").unwrap(),
+            vec![
+                Q::field("a"),
+                Q::union(vec![branch(".b"), branch("[*]")]),
+                Q::field("c")
+            ]
+        );
+        assert_eq!(
+            parse_query(r#"[*]{name: .a, "the age": .b.c , all: (.x,.y)}"#).unwrap(),
+            vec![
+                Q::any_item(),
+                Q::object(vec![
+                    ("name".into(), branch(".a")),
+                    ("the age".into(), branch(".b.c")),
+                    ("all".into(), branch("(.x,.y)")),
+                ])
+            ]
+        );
+        for (query, printed) in &[
+            (".a, .b", "(.a,.b)"),
+            ("..( .a ,.b)", "..(.a,.b)"),
+            (
+                r#"{a: .x, "b c": {d: .y[0]}}"#,
+                r#"{a: .x, "b c": {d: .y[0]}}"#,
+            ),
+            (r#"."a,b"{}"#, r#"."a,b"{}"#),
+            ("(@, [0])", "(@,[0])"),
+        ] {
+            let parsed = JSONQuery::parse(query).unwrap();
+            assert_eq!(*printed, parsed.to_string());
+            assert_eq!(parsed, JSONQuery::parse(printed).unwrap());
+        }
+        assert_eq!(
+            parse_query(".a)").unwrap_err(),
+            QueryParseErr::BadCharacter(2)
+        );
+        assert_eq!(
+            parse_query("(.a,.b").unwrap_err(),
+            QueryParseErr::UnexpectedEOF(')')
+        );
+        assert_eq!(
+            parse_query("{: .a}").unwrap_err(),
+            QueryParseErr::BadField(1)
+        );
+        assert_eq!(
+            parse_query("{a .b}").unwrap_err(),
+            QueryParseErr::Unexpected(3, ':')
+        );
+    }
+
+    #[test]
+    fn test_json_pointer() {
+        use super::parse_json_pointer;
+        assert_eq!(parse_json_pointer("").unwrap(), vec![]);
+        assert_eq!(
+            parse_json_pointer("/items/0/name").unwrap(),
+            vec![Q::field("items"), Q::index_or_key(0), Q::field("name")]
+        );
+        assert_eq!(
+            parse_json_pointer("/a~1b/m~0n/01/-//10").unwrap(),
+            vec![
+                Q::field("a/b"),
+                Q::field("m~n"),
+                Q::field("01"),
+                Q::field("-"),
+                Q::field(""),
+                Q::index_or_key(10)
+            ]
+        );
+        for pointer in &["", "/items/0/name", "/a~1b/m~0n/01/-//10", "/~01"] {
+            let query = JSONQuery::from_json_pointer(pointer).unwrap();
+            assert_eq!(Some(pointer.to_string()), query.to_json_pointer());
+        }
+        // Indices and keys that look like them are both written as tokens, and read back as IndexOrKey.
+        let lossy = JSONQuery::parse(r#"[0]."0""#).unwrap();
+        assert_eq!(Some("/0/0".to_string()), lossy.to_json_pointer());
+        assert_eq!(
+            vec![Q::index_or_key(0), Q::index_or_key(0)],
+            parse_json_pointer("/0/0").unwrap()
+        );
+        assert_eq!(
+            parse_json_pointer("items").unwrap_err(),
+            QueryParseErr::BadCharacter(0)
+        );
+        assert_eq!(
+            parse_json_pointer("/ok/a~2").unwrap_err(),
+            QueryParseErr::BadEscape(5)
+        );
+        assert_eq!(
+            parse_json_pointer("/a~").unwrap_err(),
+            QueryParseErr::BadEscape(2)
+        );
+    }
+
     #[test]
     fn test_missing_field() {
         assert_eq!(
             parse_query(".a.b.").unwrap_err(),
-            QueryParseErr::MissingField
+            QueryParseErr::MissingField(5)
+        );
+        assert_eq!(
+            parse_query("[0]..[1].").unwrap_err(),
+            QueryParseErr::MissingField(9)
         );
     }
 
@@ -192,4 +826,16 @@ mod tests {
             QueryParseErr::MissingNumber(1)
         )
     }
+
+    #[test]
+    fn test_error_positions() {
+        // Positions count characters, not bytes.
+        let err = parse_query(".é[x]").unwrap_err();
+        assert_eq!(
+            (QueryParseErr::BadArray(3), Some(3)),
+            (err.clone(), err.position())
+        );
+        assert_eq!(None, parse_query(".a[1").unwrap_err().position());
+        assert_eq!(Some(2), parse_query("...a").unwrap_err().position());
+    }
 }
@@ -0,0 +1,74 @@
+use crate::query::JSONQuery;
+use crate::query_executor::{ExecOptions, QueryExecErr, QueryExecutor};
+use crate::query_parser::QueryParseErr;
+use crate::AnySerializable;
+
+/// Many queries, run against the same target in a single serialization pass.
+///
+/// The queries are compiled into one prefix trie, so queries that start the same way share the work of matching.
+///
+/// ```
+/// use access_json::QuerySet;
+/// use serde_json::json;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let data = json!({"user": {"name": "Sam", "langs": ["rust", "ocaml"]}, "score": 3});
+///
+/// let queries = QuerySet::parse(&[".user.name", ".user.langs[*]", ".missing"])?;
+/// let expected = vec![Some(json!("Sam")), Some(json!(["rust", "ocaml"])), None];
+/// assert_eq!(expected, queries.execute(&data)?);
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug, Default, Serialize, Deserialize)]
+pub struct QuerySet {
+    queries: Vec<JSONQuery>,
+}
+
+impl QuerySet {
+    pub fn new(queries: Vec<JSONQuery>) -> Self {
+        Self { queries }
+    }
+
+    /// Parse every input with ``JSONQuery::parse``, stopping at the first error.
+    pub fn parse(inputs: &[&str]) -> Result<Self, QueryParseErr> {
+        inputs.iter().map(|input| JSONQuery::parse(input)).collect()
+    }
+
+    /// Add another query; returns the index of its result.
+    pub fn push(&mut self, query: JSONQuery) -> usize {
+        self.queries.push(query);
+        self.queries.len() - 1
+    }
+
+    pub fn queries(&self) -> &[JSONQuery] {
+        &self.queries
+    }
+
+    /// Execute every query against any serde-serializable object, which is only serialized once.
+    ///
+    /// There is one result per query, in order; each is what ``JSONQuery::execute`` would have given.
+    pub fn execute(
+        &self,
+        target: &dyn AnySerializable,
+    ) -> Result<Vec<Option<serde_json::Value>>, QueryExecErr> {
+        self.execute_with(target, &ExecOptions::default())
+    }
+
+    /// Execute every query with non-default settings; see ``JSONQuery::execute_with``.
+    pub fn execute_with(
+        &self,
+        target: &dyn AnySerializable,
+        options: &ExecOptions,
+    ) -> Result<Vec<Option<serde_json::Value>>, QueryExecErr> {
+        let mut runner = QueryExecutor::new(&self.queries, options)?;
+        runner.run(target)?;
+        Ok(runner.get_results())
+    }
+}
+
+impl std::iter::FromIterator<JSONQuery> for QuerySet {
+    fn from_iter<I: IntoIterator<Item = JSONQuery>>(iter: I) -> Self {
+        Self::new(iter.into_iter().collect())
+    }
+}
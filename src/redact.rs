@@ -0,0 +1,151 @@
+use crate::query::{JSONQuery, QueryElement};
+use crate::query_executor::{ExecOptions, QueryExecErr, QueryExecutor};
+use crate::AnySerializable;
+use serde_json::Value as JSON;
+use std::cmp::Ordering;
+
+/// What ``redact`` does with a denied value.
+#[derive(Clone, PartialEq, Eq, Debug, Serialize, Deserialize)]
+pub enum Redaction {
+    /// Put this value in its place.
+    Replace(JSON),
+    /// Leave it out: a field disappears from its object, and an element from its array.
+    Remove,
+}
+
+impl Default for Redaction {
+    fn default() -> Self {
+        Redaction::Replace(JSON::from("[redacted]"))
+    }
+}
+
+/// Serialize any serde-serializable object to a ``serde_json::Value``, except for whatever ``deny`` matches.
+///
+/// The deny queries match exactly as they would in ``JSONQuery::execute`` on the same ``serde_json::Value``, so wildcards, ``..`` and filters work too.
+/// If the whole object is denied, you get the placeholder, or null when removing.
+///
+/// ```
+/// use access_json::{redact, JSONQuery, Redaction};
+/// use serde_json::json;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let data = json!({"user": "sam", "sessions": [{"id": 1, "token": "abc"}, {"id": 2, "token": "def"}]});
+/// let deny = vec![JSONQuery::parse(".sessions[*].token")?];
+///
+/// let expected = json!({"user": "sam", "sessions": [{"id": 1, "token": "[redacted]"}, {"id": 2, "token": "[redacted]"}]});
+/// assert_eq!(expected, redact(&data, &deny, &Redaction::default())?);
+///
+/// let expected = json!({"user": "sam", "sessions": [{"id": 1}, {"id": 2}]});
+/// assert_eq!(expected, redact(&data, &deny, &Redaction::Remove)?);
+/// # Ok(())
+/// # }
+/// ```
+pub fn redact(
+    target: &dyn AnySerializable,
+    deny: &[JSONQuery],
+    redaction: &Redaction,
+) -> Result<JSON, QueryExecErr> {
+    // Serialize just once, so a root that changes between serializations can't move a secret out from under its path.
+    let mut output = serde_json::to_value(target)?;
+    let mut runner = QueryExecutor::new(deny, &ExecOptions::default())?;
+    runner.run(&output)?;
+    let mut denied: Vec<Vec<QueryElement>> = runner
+        .get_paths()
+        .into_iter()
+        .flatten()
+        .map(|(path, _)| path.elements)
+        .collect();
+    // Children before parents, and later indices before earlier ones, so removing never shifts a path we still need.
+    denied.sort_by(|a, b| compare_paths(b, a));
+    denied.dedup();
+
+    for path in denied {
+        match path.split_last() {
+            None => {
+                return Ok(match redaction {
+                    Redaction::Replace(placeholder) => placeholder.clone(),
+                    Redaction::Remove => JSON::Null,
+                })
+            }
+            Some((last, parent)) => {
+                if let Some(container) = lookup(&mut output, parent) {
+                    hide(container, last, redaction);
+                }
+            }
+        }
+    }
+    Ok(output)
+}
+
+/// Order paths by their elements; a path comes after its parent.
+fn compare_paths(a: &[QueryElement], b: &[QueryElement]) -> Ordering {
+    for (x, y) in a.iter().zip(b) {
+        let order = match (x, y) {
+            (QueryElement::ArrayItem(x), QueryElement::ArrayItem(y)) => x.cmp(y),
+            (QueryElement::Field(x), QueryElement::Field(y)) => x.cmp(y),
+            _ => Ordering::Equal,
+        };
+        if order != Ordering::Equal {
+            return order;
+        }
+    }
+    a.len().cmp(&b.len())
+}
+
+/// Find the value at a concrete path, if an earlier redaction didn't already hide it.
+fn lookup<'v>(value: &'v mut JSON, path: &[QueryElement]) -> Option<&'v mut JSON> {
+    let mut current = value;
+    for elem in path {
+        current = match elem {
+            QueryElement::Field(name) => current.as_object_mut()?.get_mut(name)?,
+            QueryElement::ArrayItem(index) => current.as_array_mut()?.get_mut(*index)?,
+            _ => return None,
+        };
+    }
+    Some(current)
+}
+
+fn hide(container: &mut JSON, elem: &QueryElement, redaction: &Redaction) {
+    match (container, elem, redaction) {
+        (JSON::Object(map), QueryElement::Field(name), Redaction::Remove) => {
+            map.remove(name);
+        }
+        (JSON::Array(items), QueryElement::ArrayItem(index), Redaction::Remove)
+            if *index < items.len() =>
+        {
+            items.remove(*index);
+        }
+        (container, elem, Redaction::Replace(placeholder)) => {
+            if let Some(found) = lookup(container, std::slice::from_ref(elem)) {
+                *found = placeholder.clone();
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_compare_paths() {
+        let path = |q: &str| JSONQuery::parse(q).unwrap().elements;
+        let mut paths = vec![
+            path(".a[1]"),
+            path(".a[10]"),
+            path(".a[1].b"),
+            path(".a[2]"),
+        ];
+        paths.sort_by(|a, b| compare_paths(b, a));
+        assert_eq!(
+            vec![
+                path(".a[10]"),
+                path(".a[2]"),
+                path(".a[1].b"),
+                path(".a[1]")
+            ],
+            paths
+        );
+    }
+}
@@ -0,0 +1,201 @@
+use crate::paths::JSONType;
+use crate::query::{JSONQuery, QueryElement};
+use crate::query_executor::{ExecOptions, QueryExecErr, QueryExecutor};
+use crate::AnySerializable;
+use serde_json::{json, Value as JSON};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The dialect of every document we write.
+const DRAFT: &str = "https://json-schema.org/draft/2020-12/schema";
+
+/// What we have seen at one location, across every sample value; ``to_json`` turns it into a JSON Schema.
+///
+/// ```
+/// use access_json::Schema;
+/// use serde_json::json;
+///
+/// # fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// let mut schema = Schema::default();
+/// schema.add(&json!({"id": 1, "tags": ["a"]}))?;
+/// schema.add(&json!({"id": 2.5, "note": null}))?;
+///
+/// let expected = json!({
+///     "$schema": "https://json-schema.org/draft/2020-12/schema",
+///     "type": "object",
+///     "properties": {
+///         "id": {"type": "number"},
+///         "note": {"type": "null"},
+///         "tags": {"type": "array", "items": {"type": "string"}},
+///     },
+///     "required": ["id"],
+/// });
+/// assert_eq!(expected, schema.to_json());
+/// # Ok(())
+/// # }
+/// ```
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct Schema {
+    types: BTreeSet<JSONType>,
+    /// How many objects we saw here, so we know which properties all of them had.
+    objects: usize,
+    /// Each property name, how many of those objects had it, and what was in it.
+    properties: BTreeMap<String, (usize, Schema)>,
+    /// What was in every array we saw here; None if they were all empty.
+    items: Option<Box<Schema>>,
+}
+
+impl Schema {
+    /// Walk one sample value and describe it.
+    pub fn infer(target: &dyn AnySerializable) -> Result<Self, QueryExecErr> {
+        let mut runner = QueryExecutor::new(&[], &ExecOptions::default())?;
+        runner.list_paths(None);
+        runner.run(target)?;
+        let listing = runner.get_listing();
+        let mut schema = Schema::default();
+        let mut pos = 0;
+        schema.observe(&listing, &mut pos);
+        Ok(schema)
+    }
+
+    /// Walk another sample value, and widen this schema so it describes that value too.
+    pub fn add(&mut self, target: &dyn AnySerializable) -> Result<(), QueryExecErr> {
+        self.merge(&Schema::infer(target)?);
+        Ok(())
+    }
+
+    /// Widen this schema so it also describes every value that ``other`` describes.
+    pub fn merge(&mut self, other: &Schema) {
+        self.types.extend(other.types.iter().copied());
+        self.objects += other.objects;
+        for (name, (count, schema)) in other.properties.iter() {
+            let (total, merged) = self.properties.entry(name.clone()).or_default();
+            *total += count;
+            merged.merge(schema);
+        }
+        if let Some(items) = &other.items {
+            self.items.get_or_insert_with(Default::default).merge(items);
+        }
+    }
+
+    /// A JSON Schema (draft 2020-12) document for every value we have seen.
+    pub fn to_json(&self) -> JSON {
+        let mut document = serde_json::Map::new();
+        document.insert("$schema".into(), JSON::from(DRAFT));
+        if let JSON::Object(schema) = self.subschema() {
+            document.extend(schema);
+        }
+        JSON::Object(document)
+    }
+
+    /// The entry at ``listing[*pos]`` is a value here; its descendants follow it, since the listing is in document order.
+    fn observe(&mut self, listing: &[(JSONQuery, Option<JSONType>)], pos: &mut usize) {
+        let (path, kind) = &listing[*pos];
+        *pos += 1;
+        if let Some(kind) = kind {
+            self.types.insert(*kind);
+            if *kind == JSONType::Object {
+                self.objects += 1;
+            }
+        }
+        let depth = path.elements.len();
+        while *pos < listing.len() && listing[*pos].0.elements.len() > depth {
+            match listing[*pos].0.elements.last() {
+                Some(QueryElement::Field(name)) => {
+                    let (count, schema) = self.properties.entry(name.clone()).or_default();
+                    *count += 1;
+                    schema.observe(listing, pos);
+                }
+                _ => self
+                    .items
+                    .get_or_insert_with(Default::default)
+                    .observe(listing, pos),
+            }
+        }
+    }
+
+    fn subschema(&self) -> JSON {
+        let mut types: Vec<&str> = self
+            .types
+            .iter()
+            // Every integer is also a number.
+            .filter(|kind| **kind != JSONType::Integer || !self.types.contains(&JSONType::Number))
+            .map(|kind| kind.name())
+            .collect();
+        let mut schema = serde_json::Map::new();
+        match types.len() {
+            0 => {}
+            1 => {
+                schema.insert("type".into(), JSON::from(types.remove(0)));
+            }
+            _ => {
+                schema.insert("type".into(), json!(types));
+            }
+        }
+        if self.types.contains(&JSONType::Object) {
+            let properties: serde_json::Map<String, JSON> = self
+                .properties
+                .iter()
+                .map(|(name, (_, schema))| (name.clone(), schema.subschema()))
+                .collect();
+            let required: Vec<&String> = self
+                .properties
+                .iter()
+                .filter(|(_, (count, _))| *count == self.objects)
+                .map(|(name, _)| name)
+                .collect();
+            schema.insert("properties".into(), JSON::Object(properties));
+            if !required.is_empty() {
+                schema.insert("required".into(), json!(required));
+            }
+        }
+        if let Some(items) = &self.items {
+            schema.insert("items".into(), items.subschema());
+        }
+        JSON::Object(schema)
+    }
+}
+
+/// Describe any serde-serializable object with a JSON Schema (draft 2020-12) document.
+///
+/// To describe many sample values at once, ``Schema::add`` each of them instead.
+pub fn infer_schema(target: &dyn AnySerializable) -> Result<JSON, QueryExecErr> {
+    Ok(Schema::infer(target)?.to_json())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_merge() {
+        let infer = |value: JSON| Schema::infer(&value).unwrap();
+        let mut schema = infer(json!([1, "two"]));
+        assert_eq!(
+            json!({"type": "array", "items": {"type": ["integer", "string"]}}),
+            schema.subschema()
+        );
+        schema.merge(&infer(json!([])));
+        schema.merge(&infer(json!(null)));
+        assert_eq!(
+            json!({"type": ["null", "array"], "items": {"type": ["integer", "string"]}}),
+            schema.subschema()
+        );
+        schema.merge(&infer(json!([{"a": 1.5}])));
+        assert_eq!(
+            json!({
+                "type": ["null", "array"],
+                "items": {
+                    "type": ["integer", "string", "object"],
+                    "properties": {"a": {"type": "number"}},
+                    "required": ["a"],
+                },
+            }),
+            schema.subschema()
+        );
+
+        let mut empty = Schema::default();
+        empty.merge(&schema);
+        assert_eq!(schema, empty);
+        assert_eq!(json!({}), Schema::default().subschema());
+    }
+}
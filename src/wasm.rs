@@ -0,0 +1,113 @@
+//! WebAssembly bindings for running queries in the browser, built with the ``wasm-bindgen`` feature.
+//!
+//! ```js
+//! const query = new Query(".items[0].name");  // throws a QueryError for a bad query
+//! query.execute({items: [{name: "a"}]});      // "a", or undefined if nothing matched
+//! query.executeJson('{"items": []}');         // JSON text, or undefined
+//! ```
+use crate::query::JSONQuery;
+use crate::query_executor::QueryExecErr;
+use crate::query_parser::QueryParseErr;
+use serde::Serialize;
+use serde_json::Value as JSON;
+use wasm_bindgen::prelude::*;
+
+/// Why a query could not be parsed or run.
+#[wasm_bindgen(getter_with_clone)]
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct QueryError {
+    pub message: String,
+    /// The character index where parsing failed, e.g., to underline it; undefined for errors while running.
+    pub position: Option<usize>,
+}
+
+impl QueryError {
+    fn parse(input: &str, err: QueryParseErr) -> Self {
+        let position = match err {
+            // Point just past the end, where the missing character belongs.
+            QueryParseErr::UnexpectedEOF(_) => Some(input.chars().count()),
+            _ => err.position(),
+        };
+        Self {
+            message: err.to_string(),
+            position,
+        }
+    }
+}
+
+impl From<QueryExecErr> for QueryError {
+    fn from(err: QueryExecErr) -> Self {
+        Self {
+            message: err.to_string(),
+            position: None,
+        }
+    }
+}
+
+/// A parsed query, ready to run against JavaScript values or JSON text.
+#[wasm_bindgen]
+pub struct Query {
+    query: JSONQuery,
+}
+
+#[wasm_bindgen]
+impl Query {
+    /// Parse a query with ``JSONQuery::parse``.
+    #[wasm_bindgen(constructor)]
+    pub fn new(query: &str) -> Result<Query, QueryError> {
+        match JSONQuery::parse(query) {
+            Ok(parsed) => Ok(Query { query: parsed }),
+            Err(err) => Err(QueryError::parse(query, err)),
+        }
+    }
+
+    /// Run against a JSON-like JavaScript value; undefined if nothing matched.
+    pub fn execute(&self, target: JsValue) -> Result<JsValue, QueryError> {
+        let target: JSON = serde_wasm_bindgen::from_value(target).map_err(|err| QueryError {
+            message: err.to_string(),
+            position: None,
+        })?;
+        match self.query.execute(&target)? {
+            // Objects become plain objects rather than Maps.
+            Some(found) => Ok(found
+                .serialize(&serde_wasm_bindgen::Serializer::json_compatible())
+                .map_err(|err| QueryError::from(QueryExecErr::Serialization(err.to_string())))?),
+            None => Ok(JsValue::UNDEFINED),
+        }
+    }
+
+    /// Run against JSON text, and give back what it found as JSON text; undefined if nothing matched.
+    #[wasm_bindgen(js_name = executeJson)]
+    pub fn execute_json(&self, json: &str) -> Result<Option<String>, QueryError> {
+        let target: JSON = serde_json::from_str(json).map_err(QueryExecErr::from)?;
+        Ok(self.query.execute(&target)?.map(|found| found.to_string()))
+    }
+
+    /// The query, written the way ``JSONQuery::parse`` reads it.
+    #[wasm_bindgen(js_name = toString)]
+    pub fn to_string_js(&self) -> String {
+        self.query.to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_wasm_query() {
+        let query = Query::new(".a[1]").unwrap();
+        assert_eq!(".a[1]", query.to_string_js());
+        assert_eq!(
+            Ok(Some("3".to_string())),
+            query.execute_json(r#"{"a": [2, 3]}"#)
+        );
+        assert_eq!(Ok(None), query.execute_json(r#"{"b": 1}"#));
+        assert_eq!(None, query.execute_json("{").unwrap_err().position);
+
+        let err = Query::new(".é[x]").err().unwrap();
+        assert_eq!(Some(3), err.position);
+        assert_eq!(Some(4), Query::new(".a[1").err().unwrap().position);
+        assert_eq!(Some(5), Query::new(".a.b.").err().unwrap().position);
+    }
+}